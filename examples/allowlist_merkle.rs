@@ -0,0 +1,45 @@
+//! Builds a series allowlist Merkle root and per-account proofs from a CSV of `account_id,quota`.
+//!
+//!     cargo run --example allowlist_merkle -- allowlist.csv > allowlist.json
+//!
+//! Pass `root` to `nft_set_series_allowlist_root` and each account's entry as `allowlist_proof`.
+
+use std::collections::HashMap;
+use std::{env, fs, process};
+
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json::json;
+use nft::merkle::tree::AllowlistTree;
+
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: allowlist_merkle <allowlist.csv>");
+        process::exit(1);
+    });
+    let csv = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        process::exit(1);
+    });
+    let tree = AllowlistTree::from_csv(&csv).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let proofs: HashMap<String, _> = tree
+        .entries()
+        .iter()
+        .map(|(account_id, _)| {
+            let (quota, proof) = tree.proof(account_id).unwrap();
+            let proof: Vec<Base64VecU8> = proof.into_iter().map(Base64VecU8::from).collect();
+            (account_id.to_string(), json!({ "quota": quota, "proof": proof }))
+        })
+        .collect();
+
+    println!(
+        "{}",
+        json!({
+            "root": Base64VecU8::from(tree.root()),
+            "proofs": proofs,
+        })
+    );
+}
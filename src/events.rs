@@ -0,0 +1,65 @@
+//! Series events in the NEP-297 format, logged next to the NEP-171 events of the token standard.
+
+use near_sdk::serde_json::json;
+
+use crate::*;
+
+const EVENT_STANDARD: &str = "nft_series";
+const EVENT_VERSION: &str = "1.0.0";
+
+fn emit_event<T: Serialize>(event: &str, data: &[T]) {
+    let event = json!({
+        "standard": EVENT_STANDARD,
+        "version": EVENT_VERSION,
+        "event": event,
+        "data": data,
+    });
+    env::log_str(&format!("EVENT_JSON:{}", event));
+}
+
+/// The royalty of a series changed, payouts of its tokens should be refreshed.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeriesRoyaltyUpdate<'a> {
+    pub token_series_id: &'a TokenSeriesId,
+    pub royalty: &'a HashMap<AccountId, u32>,
+}
+
+impl SeriesRoyaltyUpdate<'_> {
+    pub fn emit(self) {
+        emit_event("series_royalty_update", &[self])
+    }
+}
+
+/// The creator of a series invited another account to take it over, or withdrew the invitation.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeriesCreatorProposed<'a> {
+    pub token_series_id: &'a TokenSeriesId,
+    pub creator_id: &'a AccountId,
+    pub proposed_creator_id: Option<&'a AccountId>,
+}
+
+impl SeriesCreatorProposed<'_> {
+    pub fn emit(self) {
+        emit_event("series_creator_proposed", &[self])
+    }
+}
+
+/// A series and its unclaimed proceeds moved to a new creator.
+#[must_use]
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeriesCreatorTransfer<'a> {
+    pub token_series_id: &'a TokenSeriesId,
+    pub old_creator_id: &'a AccountId,
+    pub new_creator_id: &'a AccountId,
+}
+
+impl SeriesCreatorTransfer<'_> {
+    pub fn emit(self) {
+        emit_event("series_creator_transfer", &[self])
+    }
+}
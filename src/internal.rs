@@ -1,18 +1,15 @@
-
-use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
-
 use crate::*;
+use crate::utils::{assert_valid_currency, assert_valid_royalty, check_sale_open, merge_token_metadata};
 
 impl Contract {
     pub(crate) fn internal_create_series(
         &mut self, 
         creator_id: AccountId,
         token_metadata: TokenMetadata,
-        mint_price: Option<U128>, 
-        ft_token_id: Option<AccountId>,
         royalty: Option<HashMap<AccountId, u32>>,
     ) -> TokenSeriesId {
-        
+        // ids follow the number of series, which only counts migrated ones until the migration is done
+        assert!(self.series_migration.is_none(), " series migration in progress");
 
         let token_series_id = format!("{}", (self.token_series_by_id.len() + 1));
 
@@ -25,79 +22,75 @@ impl Contract {
         assert!(title.is_some(), " token_metadata.title is required");
         
 
-        let mut total_perpetual = 0;
-        let mut total_accounts = 0;
-        let royalty_res: HashMap<AccountId, u32> = if let Some(royalty) = royalty {
-            for (_ , v) in royalty.iter() {
-                total_perpetual += *v;
-                total_accounts += 1;
-            }
-            royalty
-        } else {
-            HashMap::new()
-        };
-
-        assert!(total_accounts <= 10, " royalty exceeds 10 accounts");
-
-        assert!(
-            total_perpetual <= 9000,
-            "Exceeds maximum royalty -> 9000",
-        );
+        let royalty_res: HashMap<AccountId, u32> = royalty.unwrap_or_default();
+        assert_valid_royalty(&royalty_res);
 
-        let token_series = TokenSeries{
-            metadata: token_metadata.clone(),
-            creator_id: creator_id.clone(),
-            tokens: UnorderedSet::new(
-                StorageKey::TokensBySeriesInner {
-                    token_series: token_series_id.clone(),
-                }
-                .try_to_vec()
-                .unwrap(),
-            ),
-            price: match mint_price {
-                Some(v) => Some(u128::from(v)),
-                None => None
-            },
-            ft_token_id: ft_token_id,
-            is_mintable: true,
-            royalty: royalty_res.clone(),
-        };
+        let token_series = TokenSeries::new(&token_series_id, token_metadata, creator_id, royalty_res);
 
         self.token_series_by_id.insert(&token_series_id, &token_series);
         token_series_id
     }
 
 
+    /// Mints the next edition of a series to `receiver_id`. Mints paid by `minter_id` count
+    /// towards the series per-account limit, except when the minter is the creator.
     pub(crate) fn internal_nft_mint_series(
         &mut self, 
-        sender_id: AccountId,
         token_series_id: TokenSeriesId, 
-        receiver_id: AccountId
+        receiver_id: AccountId,
+        minter_id: &AccountId
     ) -> TokenId {
-        let account = self.accounts.get(&sender_id);
-        assert!(account.is_some(), "not registered");
-        let refund = account.unwrap() - self.tokens.extra_storage_in_bytes_per_token as u128 * env::storage_byte_cost();
-        assert!(refund > 0, "not enough deposit");
         let mut token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
-        assert!(
-            token_series.is_mintable,
-            " Token series is not mintable"
-        );
+        let token_id = self.internal_mint_edition(&token_series_id, &mut token_series, receiver_id, minter_id);
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        token_id
+    }
+
+    /// Mints the next edition of an already loaded series, which the caller stores afterwards.
+    /// Lets a batch mint read and write its series once.
+    pub(crate) fn internal_mint_edition(
+        &mut self,
+        token_series_id: &TokenSeriesId,
+        token_series: &mut TokenSeries,
+        receiver_id: AccountId,
+        minter_id: &AccountId
+    ) -> TokenId {
+        if let Err(err) = self.internal_check_mintable(token_series_id, token_series, minter_id) {
+            env::panic_str(&err);
+        }
+
+        if *minter_id != token_series.creator_id {
+            let key = (token_series_id.clone(), minter_id.clone());
+            let minted = self.minted_per_account.get(&key).unwrap_or(0);
+            self.minted_per_account.insert(&key, &(minted + 1));
+        }
     
-        let num_tokens = token_series.tokens.len();
         let max_copies = token_series.metadata.copies.unwrap_or(u64::MAX);
-        assert!(num_tokens < max_copies, "Series supply maxed");
+
+        let edition = match token_series.burned_editions.last() {
+            Some(_) if token_series.remint_burned => token_series.burned_editions.pop().unwrap(),
+            _ => {
+                token_series.minted += 1;
+                token_series.minted
+            }
+        };
     
-        if (num_tokens + 1) >= max_copies {
+        let token_id = format!("{}{}{}", token_series_id, TOKEN_DELIMETER, edition);
+        token_series.tokens.insert(&token_id);
+
+        let now = env::block_timestamp();
+        let locked_until = token_series.transfer_locked_until.unwrap_or(0)
+            .max(token_series.lock_duration_after_mint.map_or(0, |duration| now.saturating_add(duration)));
+        if locked_until > now {
+            self.locked_until_by_id.insert(&token_id, &locked_until);
+        }
+        if token_series.issued_supply() >= max_copies {
             token_series.is_mintable = false;
         }
     
-        let token_id = format!("{}{}{}", &token_series_id, TOKEN_DELIMETER, num_tokens + 1);
-        token_series.tokens.insert(&token_id);
-        self.token_series_by_id.insert(&token_series_id, &token_series);
-    
-        // you can add custom metadata to each token here
-        let metadata = Some(token_series.metadata);
+        // only per-token fields are stored, the rest is composed from the series at view time
+        let issued_at = (env::block_timestamp() / 1_000_000).to_string();
+        let metadata = Some(token_metadata_overrides(Some(issued_at)));
     
         //let token = self.tokens.mint(token_id, receiver_id, metadata);
         // From : https://github.com/near/near-sdk-rs/blob/master/near-contract-standards/src/non_fungible_token/core/core_impl.rs#L359
@@ -120,72 +113,454 @@ impl Contract {
              token_ids.insert(&token_id);
              tokens_per_owner.insert(&owner_id, &token_ids);
         }
-        self.accounts.insert(&sender_id, &0);
-        Promise::new(sender_id).transfer(refund);
         token_id
     }
 
-    pub(crate) fn internal_mint_with_token(&mut self, sender_id: AccountId, ft_token_id: AccountId, amount: u128, token_series_id: TokenSeriesId, receiver_id: AccountId) -> (TokenId, Balance) {
-        let account = self.accounts.get(&sender_id);
-        assert!(account.is_some(), "not registered");
+    /// Checks everything that could fail a mint of `minter_id` before any state changes.
+    pub(crate) fn internal_check_mintable(&self, token_series_id: &TokenSeriesId, token_series: &TokenSeries, minter_id: &AccountId) -> Result<(), String> {
+        if !token_series.is_mintable {
+            return Err(" Token series is not mintable".to_string());
+        }
+        if *minter_id != token_series.creator_id {
+            if let Some(max_per_account) = token_series.max_per_account {
+                let minted = self.minted_per_account.get(&(token_series_id.clone(), minter_id.clone())).unwrap_or(0);
+                if minted >= max_per_account {
+                    return Err(format!(" mint limit per account reached : {}", max_per_account));
+                }
+            }
+        }
+        if token_series.issued_supply() >= token_series.metadata.copies.unwrap_or(u64::MAX) {
+            return Err("Series supply maxed".to_string());
+        }
+        Ok(())
+    }
+
+    /// Mints a token paid for with `amount` of `ft_token_id` and returns the unused amount.
+    /// All checks run before any state changes, so on error the full amount can be refunded.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn internal_mint_with_token(&mut self, sender_id: AccountId, ft_token_id: AccountId, amount: u128, token_series_id: TokenSeriesId, receiver_id: AccountId, allowlist_proof: Option<&AllowlistProof>, referrer_id: Option<&AccountId>) -> Result<(TokenId, Balance), String> {
+        if !self.accounts.contains_key(&sender_id) {
+            return Err("not registered".to_string());
+        }
         
-        let token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
-        let price: u128 = token_series.price.expect(" not for sale");
-        assert!(token_series.ft_token_id.clone().unwrap() == ft_token_id, "uncorrect token");
+        let token_series = self.token_series_by_id.get(&token_series_id).ok_or(" Token series not exist")?;
+        check_sale_open(&token_series)?;
+        let price = self.internal_sale_price(&token_series_id, &token_series, &sender_id, ft_token_id.as_str(), allowlist_proof)?;
+        if amount < price {
+            return Err(format!(" amount is less than price : {}", price));
+        }
+        self.internal_check_mintable(&token_series_id, &token_series, &sender_id)?;
+        // storage is settled after the mint, so make sure it cannot fail then
+        let storage_usage = self.token_storage_usage
+            + self.sale_storage_usage
+            + self.proceeds_storage_usage * (token_series.primary_split.len() as u64 + 1);
+        let storage_cost = Balance::from(storage_usage) * env::storage_byte_cost();
+        if self.internal_storage_available(&sender_id) < storage_cost {
+            return Err(format!("not enough storage balance, required: {}", storage_cost));
+        }
+        
+        self.internal_credit_sale(&token_series_id, &token_series, ft_token_id.as_str(), price, &sender_id, referrer_id);
+        let token_id = self.internal_nft_mint_series(token_series_id, receiver_id, &sender_id);
+        Ok((token_id, amount - price))
+    }
+
+    /// Mints a token of an already loaded series paid for in NEAR from the storage balance of the caller,
+    /// the caller stores the series afterwards.
+    pub(crate) fn internal_mint_with_near(&mut self, token_series_id: &TokenSeriesId, token_series: &mut TokenSeries, receiver_id: AccountId, allowlist_proof: Option<&AllowlistProof>, referrer_id: Option<&AccountId>) -> TokenId {
+        let sender_id = env::predecessor_account_id();
+        let price = check_sale_open(token_series)
+            .and_then(|_| self.internal_sale_price(token_series_id, token_series, &sender_id, NEAR, allowlist_proof))
+            .unwrap_or_else(|err| env::panic_str(&err));
         assert!(
-            amount >= price,
+            self.internal_storage_available(&sender_id) >= price,
             " amount is less than price : {}",
             price
         );
-        
-        let token_id = self.internal_nft_mint_series(sender_id, token_series_id, receiver_id);
-        (token_id, amount - price)
+        let balance = self.accounts.get(&sender_id).unwrap();
+        self.accounts.insert(&sender_id, &(balance - price));
+        self.internal_credit_sale(token_series_id, token_series, NEAR, price, &sender_id, referrer_id);
+        self.internal_mint_edition(token_series_id, token_series, receiver_id, &sender_id)
     }
 
-    pub(crate) fn internal_mint_with_near(&mut self, token_series_id: TokenSeriesId, receiver_id: AccountId) -> TokenId {
-        let sender_id = env::predecessor_account_id();
-        let amount = self.accounts.get(&sender_id).unwrap();
-        let token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
-        let price: u128 = token_series.price.expect(" not for sale");
-        self.accounts.insert(&sender_id, &(amount - price));
-        let token_id = self.internal_nft_mint_series(sender_id, token_series_id, receiver_id);
-        token_id
+    pub(crate) fn measure_account_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+        self.accounts.insert(&tmp_account_id, &0u128);
+        self.account_storage_usage = env::storage_usage() - initial_storage_usage;
+        self.accounts.remove(&tmp_account_id);
+    }
+
+    pub(crate) fn measure_token_storage_usage(&mut self) {
+        let initial_storage_usage = env::storage_usage();
+        let tmp_token_id = "a".repeat(64);
+        let tmp_owner_id = AccountId::new_unchecked("a".repeat(64));
+
+        self.tokens.owner_by_id.insert(&tmp_token_id, &tmp_owner_id);
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.insert(&tmp_token_id, &token_metadata_overrides(Some(u64::MAX.to_string())));
+        }
+        let mut owner_tokens = UnorderedSet::new(StorageKey::TokensPerOwner {
+            account_hash: env::sha256(tmp_owner_id.as_bytes()),
+        });
+        owner_tokens.insert(&tmp_token_id);
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            tokens_per_owner.insert(&tmp_owner_id, &owner_tokens);
+        }
+        let mut series_tokens = UnorderedSet::new(
+            StorageKey::TokensBySeriesInner {
+                token_series: tmp_token_id.clone(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        series_tokens.insert(&tmp_token_id);
+
+        self.token_storage_usage = env::storage_usage() - initial_storage_usage;
+
+        series_tokens.clear();
+        owner_tokens.clear();
+        if let Some(tokens_per_owner) = &mut self.tokens.tokens_per_owner {
+            tokens_per_owner.remove(&tmp_owner_id);
+        }
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            token_metadata_by_id.remove(&tmp_token_id);
+        }
+        self.tokens.owner_by_id.remove(&tmp_token_id);
+    }
+
+    pub(crate) fn measure_sale_storage_usage(&mut self) {
+        let tmp_id = "a".repeat(64);
+        let tmp_account_id = AccountId::new_unchecked("a".repeat(64));
+
+        let initial_storage_usage = env::storage_usage();
+        self.locked_until_by_id.insert(&tmp_id, &u64::MAX);
+        self.minted_per_account.insert(&(tmp_id.clone(), tmp_account_id.clone()), &u32::MAX);
+        let mut referrer = Referrer::default();
+        referrer.earned.insert(tmp_id.clone(), Balance::MAX);
+        referrer.claimable.insert(tmp_id.clone(), Balance::MAX);
+        self.referrers.insert(&tmp_account_id, &referrer);
+        self.sale_storage_usage = env::storage_usage() - initial_storage_usage;
+
+        let initial_storage_usage = env::storage_usage();
+        let mut by_series = HashMap::new();
+        by_series.insert(tmp_id.clone(), Balance::MAX);
+        let mut proceeds = HashMap::new();
+        proceeds.insert(tmp_id.clone(), by_series);
+        self.proceeds.insert(&tmp_account_id, &proceeds);
+        self.proceeds_storage_usage = env::storage_usage() - initial_storage_usage;
+
+        self.proceeds.remove(&tmp_account_id);
+        self.referrers.remove(&tmp_account_id);
+        self.minted_per_account.remove(&(tmp_id.clone(), tmp_account_id));
+        self.locked_until_by_id.remove(&tmp_id);
+    }
+
+    /// Composes the full metadata of a token from its series and the per-token overrides.
+    pub(crate) fn internal_token_metadata(&self, token_id: &TokenId, overrides: Option<TokenMetadata>) -> TokenMetadata {
+        let token_series_id = token_id.split(TOKEN_DELIMETER).next().unwrap();
+        let token_series = self.token_series_by_id.get(&token_series_id.to_string()).expect("Token series not exist");
+        let overrides = overrides.unwrap_or_else(|| token_metadata_overrides(None));
+        let metadata = token_metadata_for_edition(&token_series, edition_of(token_id), None);
+        merge_token_metadata(metadata, overrides)
+    }
+
+    /// Rejects tokens of soulbound series, which can never change hands or be approved.
+    pub(crate) fn internal_assert_not_soulbound(&self, token_id: &TokenId) {
+        let token_series_id = token_id.split(TOKEN_DELIMETER).next().unwrap();
+        let token_series = self.token_series_by_id.get(&token_series_id.to_string()).expect("Token series not exist");
+        assert!(token_series.transferable, " Token is non-transferable");
+    }
+
+    /// Rejects transfers of soulbound tokens and of tokens still under their transfer lock.
+    pub(crate) fn internal_assert_transferable(&self, token_id: &TokenId) {
+        self.internal_assert_not_soulbound(token_id);
+        if let Some(locked_until) = self.locked_until_by_id.get(token_id) {
+            assert!(
+                env::block_timestamp() >= locked_until,
+                " Token is locked until {}",
+                locked_until
+            );
+        }
     }
 
-    pub(crate) fn internal_set_price(&mut self, token_series_id: TokenSeriesId, mint_price: Option<U128>, ft_token_id: Option<AccountId>) -> Option<U128> {
-        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+    pub(crate) fn internal_with_series_metadata(&self, mut token: Token) -> Token {
+        token.metadata = Some(self.internal_token_metadata(&token.token_id, token.metadata));
+        token
+    }
+
+    pub(crate) fn internal_min_storage_balance(&self) -> Balance {
+        Balance::from(self.account_storage_usage) * env::storage_byte_cost()
+    }
+
+    pub(crate) fn internal_storage_available(&self, account_id: &AccountId) -> Balance {
+        let balance = self.accounts.get(account_id).expect("not registered");
+        balance.saturating_sub(self.internal_min_storage_balance())
+    }
+
+    /// Adds `amount` to the storage balance of `account_id`, registering the account if needed.
+    pub(crate) fn internal_storage_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        match self.accounts.get(account_id) {
+            Some(balance) => {
+                self.accounts.insert(account_id, &(balance + amount));
+            }
+            None => {
+                assert!(
+                    amount >= self.internal_min_storage_balance(),
+                    "The attached deposit is less than the minimum storage balance"
+                );
+                self.accounts.insert(account_id, &amount);
+            }
+        }
+    }
+
+    /// Settles the storage used since `initial_storage_usage` against the storage balance of `account_id`.
+    /// Series setters charge their creator this way, so storage is paid from the creator's storage balance.
+    /// Freed storage is credited back if the account is registered.
+    pub(crate) fn internal_storage_charge(&mut self, account_id: &AccountId, initial_storage_usage: StorageUsage) {
+        let storage_usage = env::storage_usage();
+        if storage_usage > initial_storage_usage {
+            let cost = Balance::from(storage_usage - initial_storage_usage) * env::storage_byte_cost();
+            assert!(
+                self.internal_storage_available(account_id) >= cost,
+                "not enough storage balance, required: {}",
+                cost
+            );
+            let balance = self.accounts.get(account_id).unwrap();
+            self.accounts.insert(account_id, &(balance - cost));
+        } else if storage_usage < initial_storage_usage {
+            if let Some(balance) = self.accounts.get(account_id) {
+                let refund = Balance::from(initial_storage_usage - storage_usage) * env::storage_byte_cost();
+                self.accounts.insert(account_id, &(balance + refund));
+            }
+        }
+    }
+
+    pub(crate) fn internal_credit_proceeds(&mut self, token_series_id: &TokenSeriesId, creator_id: &AccountId, currency: &str, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let mut proceeds = self.proceeds.get(creator_id).unwrap_or_default();
+        *proceeds
+            .entry(currency.to_string())
+            .or_default()
+            .entry(token_series_id.clone())
+            .or_insert(0) += amount;
+        self.proceeds.insert(creator_id, &proceeds);
+    }
+
+    /// Splits a primary sale between the platform fee, the referral reward and the creator proceeds.
+    /// Buyers cannot refer themselves.
+    pub(crate) fn internal_credit_sale(
+        &mut self,
+        token_series_id: &TokenSeriesId,
+        token_series: &TokenSeries,
+        currency: &str,
+        price: Balance,
+        buyer_id: &AccountId,
+        referrer_id: Option<&AccountId>
+    ) {
+        let fee = price * self.platform_fee_bps as u128 / 10_000;
+        if fee > 0 {
+            *self.platform_fees.entry(currency.to_string()).or_insert(0) += fee;
+        }
+        let mut creator_share = price - fee;
+
+        match referrer_id {
+            Some(referrer_id) if token_series.referral_bps > 0 && referrer_id != buyer_id => {
+                let reward = (price * token_series.referral_bps as u128 / 10_000).min(creator_share);
+                let mut referrer = self.referrers.get(referrer_id).unwrap_or_default();
+                referrer.mints += 1;
+                if reward > 0 {
+                    *referrer.earned.entry(currency.to_string()).or_insert(0) += reward;
+                    *referrer.claimable.entry(currency.to_string()).or_insert(0) += reward;
+                }
+                self.referrers.insert(referrer_id, &referrer);
+                creator_share -= reward;
+            }
+            _ => {}
+        }
+        self.internal_credit_primary_split(token_series_id, token_series, currency, creator_share);
+    }
+
+    /// Credits `amount` of proceeds to the primary split recipients.
+    /// Rounding dust goes to the creator.
+    pub(crate) fn internal_credit_primary_split(&mut self, token_series_id: &TokenSeriesId, token_series: &TokenSeries, currency: &str, amount: Balance) {
+        let mut remaining = amount;
+        for (account_id, bps) in token_series.primary_split.iter() {
+            let share = amount * *bps as u128 / 10_000;
+            self.internal_credit_proceeds(token_series_id, account_id, currency, share);
+            remaining -= share;
+        }
+        self.internal_credit_proceeds(token_series_id, &token_series.creator_id, currency, remaining);
+    }
 
-        if mint_price.is_none() || ft_token_id.is_none() {
-            token_series.price = None;
-            token_series.ft_token_id = None;
+    pub(crate) fn internal_take_referral_rewards(&mut self, referrer_id: &AccountId, currency: &str) -> Balance {
+        let mut referrer = self.referrers.get(referrer_id).unwrap_or_default();
+        let amount = referrer.claimable.remove(currency).unwrap_or(0);
+        self.referrers.insert(referrer_id, &referrer);
+        amount
+    }
+
+    pub(crate) fn internal_credit_referral_rewards(&mut self, referrer_id: &AccountId, currency: &str, amount: Balance) {
+        let mut referrer = self.referrers.get(referrer_id).unwrap_or_default();
+        *referrer.claimable.entry(currency.to_string()).or_insert(0) += amount;
+        self.referrers.insert(referrer_id, &referrer);
+    }
+
+    /// Moves the unclaimed proceeds of a series in every currency from `from_id` to `to_id`.
+    pub(crate) fn internal_move_series_proceeds(&mut self, token_series_id: &TokenSeriesId, from_id: &AccountId, to_id: &AccountId) {
+        let mut proceeds = match self.proceeds.get(from_id) {
+            Some(proceeds) => proceeds,
+            None => return,
+        };
+        let mut moved: Vec<(String, Balance)> = Vec::new();
+        for (currency, by_series) in proceeds.iter_mut() {
+            if let Some(amount) = by_series.remove(token_series_id) {
+                moved.push((currency.clone(), amount));
+            }
+        }
+        proceeds.retain(|_, by_series| !by_series.is_empty());
+        if proceeds.is_empty() {
+            self.proceeds.remove(from_id);
         } else {
-            token_series.price = Some(mint_price.unwrap().into());
-            token_series.ft_token_id = Some(ft_token_id.clone().unwrap());
+            self.proceeds.insert(from_id, &proceeds);
         }
 
-        self.token_series_by_id.insert(&token_series_id, &token_series);
-        if let Some(price) = mint_price { Some(price) } else { None }
+        for (currency, amount) in moved {
+            self.internal_credit_proceeds(token_series_id, to_id, &currency, amount);
+        }
     }
 
-    pub(crate) fn internal_nft_payout(&self, token_series_id: TokenId, balance: u128) -> Payout {
-        let token_series = self.token_series_by_id.get(&token_series_id).expect("no type");
-        let royalty = token_series.royalty;
-        let balance_u128: u128 = balance.into();
-    
-        let mut payout: Payout = Payout { payout: HashMap::new() };
-        let mut total_perpetual = 0;
-    
-        for (k, v) in royalty.iter() {
-            let key = k.clone();
-            payout.payout.insert(key, royalty_to_payout(*v, balance_u128));
-            total_perpetual += *v;
-            
+    pub(crate) fn internal_take_proceeds(&mut self, creator_id: &AccountId, currency: &str) -> HashMap<TokenSeriesId, Balance> {
+        let mut proceeds = self.proceeds.get(creator_id).unwrap_or_default();
+        let taken = proceeds.remove(currency).unwrap_or_default();
+        if proceeds.is_empty() {
+            self.proceeds.remove(creator_id);
+        } else {
+            self.proceeds.insert(creator_id, &proceeds);
         }
-        payout.payout.insert(token_series.creator_id, royalty_to_payout(10000 - total_perpetual, balance_u128));
-        payout
+        taken
     }
 
-    
+    /// Returns the price `buyer_id` pays in `currency` for the next token, enforcing the allowlist quota during the presale.
+    pub(crate) fn internal_sale_price(
+        &self,
+        token_series_id: &TokenSeriesId,
+        token_series: &TokenSeries,
+        buyer_id: &AccountId,
+        currency: &str,
+        allowlist_proof: Option<&AllowlistProof>
+    ) -> Result<Balance, String> {
+        match &token_series.presale {
+            Some(presale) if env::block_timestamp() < presale.ends_at.0 => {
+                let key = (token_series_id.clone(), buyer_id.clone());
+                let quota = match (&token_series.allowlist_root, allowlist_proof) {
+                    (Some(root), Some(allowlist_proof)) => {
+                        let proof: Vec<Vec<u8>> = allowlist_proof.proof.iter().map(|node| node.0.clone()).collect();
+                        if !merkle::verify_proof(root, merkle::leaf_hash(buyer_id, allowlist_proof.quota), &proof) {
+                            return Err(" invalid allowlist proof".to_string());
+                        }
+                        allowlist_proof.quota
+                    }
+                    _ => self.allowlist.get(&key).ok_or(" not on the allowlist")?,
+                };
+                let minted = self.minted_per_account.get(&key).unwrap_or(0);
+                if minted >= quota {
+                    return Err(format!(" allowlist quota reached : {}", quota));
+                }
+                presale
+                    .prices
+                    .get(currency)
+                    .map(|price| price.0)
+                    .or_else(|| token_series.current_price(currency))
+                    .ok_or_else(|| format!(" not for sale in {}", currency))
+            }
+            _ => token_series.current_price(currency).ok_or_else(|| format!(" not for sale in {}", currency)),
+        }
+    }
 
-}
+    pub(crate) fn internal_set_mint_window(&mut self, token_series_id: &TokenSeriesId, mint_start: Option<U64>, mint_end: Option<U64>) {
+        let mut token_series = self.token_series_by_id.get(token_series_id).expect("Token series not exist");
+        if let (Some(start), Some(end)) = (mint_start, mint_end) {
+            assert!(start.0 < end.0, " mint_start must be before mint_end");
+        }
+        token_series.mint_start = mint_start.map(u64::from);
+        token_series.mint_end = mint_end.map(u64::from);
+        self.token_series_by_id.insert(token_series_id, &token_series);
+    }
+
+    /// Sets the price of the series in one currency, `None` stops accepting that currency.
+    pub(crate) fn internal_set_price(&mut self, token_series_id: &TokenSeriesId, currency: String, pricing: Option<PricingMode>) -> Option<U128> {
+        let mut token_series = self.token_series_by_id.get(token_series_id).expect("Token series not exist");
+
+        match pricing {
+            Some(pricing) => {
+                assert_valid_currency(&currency);
+                assert!(
+                    token_series.prices.contains_key(&currency) || token_series.prices.len() < MAX_SERIES_CURRENCIES,
+                    " price list exceeds {} currencies",
+                    MAX_SERIES_CURRENCIES
+                );
+                pricing.assert_valid(token_series.metadata.copies);
+                token_series.prices.insert(currency.clone(), pricing);
+            }
+            None => {
+                token_series.prices.remove(&currency);
+            }
+        }
 
+        self.token_series_by_id.insert(token_series_id, &token_series);
+        token_series.current_price(&currency).map(U128::from)
+    }
+
+    /// Replaces the whole price list of the series, an empty list takes it off sale.
+    pub(crate) fn internal_set_prices(&mut self, token_series_id: &TokenSeriesId, prices: HashMap<String, PricingMode>) {
+        let mut token_series = self.token_series_by_id.get(token_series_id).expect("Token series not exist");
+        assert!(prices.len() <= MAX_SERIES_CURRENCIES, " price list exceeds {} currencies", MAX_SERIES_CURRENCIES);
+        for (currency, pricing) in prices.iter() {
+            assert_valid_currency(currency);
+            pricing.assert_valid(token_series.metadata.copies);
+        }
+        token_series.prices = prices;
+        self.token_series_by_id.insert(token_series_id, &token_series);
+    }
+
+    /// Splits `balance` between the series royalty recipients and `owner_id`.
+    /// The owner receives the remainder, including any rounding dust and their own royalty share.
+    pub(crate) fn internal_nft_payout(
+        &self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+        max_len_payout: Option<u32>
+    ) -> Payout {
+        let token_series_id = token_id.split(TOKEN_DELIMETER).next().unwrap();
+        let royalty = self.token_series_by_id.get(&token_series_id.to_string()).expect("no type").royalty;
+
+        let mut payout: Payout = Payout { payout: HashMap::new() };
+        let mut total_royalty: Balance = 0;
+
+        for (account_id, bps) in royalty.iter() {
+            if account_id == owner_id {
+                continue;
+            }
+            let amount = royalty_to_payout(*bps, balance);
+            if amount.0 == 0 {
+                continue;
+            }
+            total_royalty += amount.0;
+            payout.payout.insert(account_id.clone(), amount);
+        }
+
+        assert!(total_royalty <= balance, "Total payout overflow");
+        payout.payout.insert(owner_id.clone(), U128(balance - total_royalty));
+
+        if let Some(max_len_payout) = max_len_payout {
+            assert!(
+                payout.payout.len() as u32 <= max_len_payout,
+                "Market cannot payout to that many receivers"
+            );
+        }
+        payout
+    }
+}
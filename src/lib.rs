@@ -5,18 +5,21 @@ use near_contract_standards::non_fungible_token::events::{NftMint, NftBurn};
 use near_contract_standards::non_fungible_token::metadata::{
     NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata, NFT_METADATA_SPEC,
 };
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
+use near_contract_standards::non_fungible_token::core::{NonFungibleTokenCore, NonFungibleTokenResolver};
+use near_contract_standards::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
 use near_contract_standards::non_fungible_token::NonFungibleToken;
 use near_contract_standards::non_fungible_token::{Token, TokenId};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, UnorderedMap, UnorderedSet, LookupMap};
-use near_sdk::json_types::{U128, U64};
+use near_sdk::collections::{LazyOption, UnorderedMap, UnorderedSet, LookupMap, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Serialize, Deserialize};
 use near_sdk::serde_json::json;
 use near_sdk::{
-    assert_one_yocto, env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue, Balance,
+    assert_one_yocto, env, ext_contract, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue, Balance, Gas, StorageUsage,
 };
 
-use crate::utils::{refund_extra_storage_deposit, royalty_to_payout};
+use crate::utils::{edition_of, royalty_to_payout, token_metadata_for_edition, token_metadata_overrides};
 
 pub mod payout;
 pub mod utils;
@@ -24,6 +27,12 @@ pub mod internal;
 pub mod view;
 pub mod series;
 pub mod resolver;
+pub mod proceeds;
+pub mod storage;
+pub mod token;
+pub mod merkle;
+pub mod events;
+mod migration;
 
 pub type TokenSeriesId = String;
 pub type PayoutHashMap = HashMap<AccountId, U128>;
@@ -31,6 +40,18 @@ pub const TOKEN_DELIMETER: char = ':';
 pub const TITLE_DELIMETER: &str = " #";
 pub const EDITION_DELIMETER: &str = "/";
 pub const NEAR: &str = "near";
+/// Payment currencies a series price list, or its presale, may hold
+pub const MAX_SERIES_CURRENCIES: usize = 10;
+/// Steepest exponential curve, 10% per token
+pub const MAX_CURVE_GROWTH_BPS: u32 = 1_000;
+/// Platform fee cap of 10%, creators keep the rest of every primary sale
+pub const MAX_PLATFORM_FEE_BPS: u32 = 1_000;
+/// Receivers of one `nft_batch_mint`. The series is read and written once per batch, each receiver
+/// still adds its token, sale and proceeds records.
+pub const MAX_BATCH_MINT_SIZE: u64 = 50;
+
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(10_000_000_000_000);
 
 
 
@@ -40,15 +61,311 @@ pub struct Payout {
     pub payout: PayoutHashMap
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TitleFormat {
+    pub title_delimeter: String,
+    /// Appends `{edition_delimeter}{copies}` to the title when set and the series has limited copies
+    pub edition_delimeter: Option<String>
+}
+
+impl Default for TitleFormat {
+    fn default() -> Self {
+        TitleFormat {
+            title_delimeter: TITLE_DELIMETER.to_string(),
+            edition_delimeter: Some(EDITION_DELIMETER.to_string())
+        }
+    }
+}
+
+/// How the public mint price of a series is determined
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde", tag = "mode", rename_all = "snake_case")]
+pub enum PricingMode {
+    Fixed {
+        price: U128
+    },
+    /// Price falls linearly from `start_price` at `start_at` to `floor_price` after `duration` nanoseconds.
+    /// With `step_interval` set it only drops once per interval, stepping down to the floor.
+    DutchAuction {
+        start_price: U128,
+        floor_price: U128,
+        start_at: U64,
+        duration: U64,
+        step_interval: Option<U64>
+    },
+    /// Open editions only: the n-th token (counting from 0) costs `base_price + n * increment`
+    LinearCurve {
+        base_price: U128,
+        increment: U128
+    },
+    /// Open editions only: every token costs `growth_bps` basis points more than the previous one
+    ExponentialCurve {
+        base_price: U128,
+        growth_bps: u32
+    }
+}
+
+impl PricingMode {
+    pub(crate) fn assert_valid(&self, copies: Option<u64>) {
+        match self {
+            PricingMode::DutchAuction { start_price, floor_price, duration, step_interval, .. } => {
+                assert!(start_price.0 >= floor_price.0, " start_price must not be less than floor_price");
+                assert!(duration.0 > 0, " duration must be positive");
+                if let Some(step_interval) = step_interval {
+                    assert!(step_interval.0 > 0, " step_interval must be positive");
+                }
+            }
+            PricingMode::LinearCurve { .. } => {
+                assert!(copies.is_none(), " bonding curves are only for open editions");
+            }
+            PricingMode::ExponentialCurve { growth_bps, .. } => {
+                assert!(copies.is_none(), " bonding curves are only for open editions");
+                assert!(
+                    *growth_bps <= MAX_CURVE_GROWTH_BPS,
+                    " growth_bps exceeds {}",
+                    MAX_CURVE_GROWTH_BPS
+                );
+            }
+            PricingMode::Fixed { .. } => {}
+        }
+    }
+
+    /// Price of a mint at block timestamp `now` when `supply` tokens of the series exist,
+    /// `None` once a bonding curve grows past what a balance can hold
+    pub(crate) fn price_at(&self, now: u64, supply: u64) -> Option<Balance> {
+        match self {
+            PricingMode::Fixed { price } => Some(price.0),
+            PricingMode::DutchAuction { start_price, floor_price, start_at, duration, step_interval } => {
+                let duration = duration.0;
+                let mut elapsed = now.saturating_sub(start_at.0).min(duration);
+                match step_interval {
+                    Some(step_interval) if elapsed < duration => elapsed -= elapsed % step_interval.0,
+                    _ => {}
+                }
+                // drop * elapsed / duration, split so the product cannot overflow
+                let drop = start_price.0 - floor_price.0;
+                let (quotient, remainder) = (drop / duration as u128, drop % duration as u128);
+                let decayed = quotient * elapsed as u128 + remainder * elapsed as u128 / duration as u128;
+                Some(start_price.0 - decayed)
+            }
+            PricingMode::LinearCurve { base_price, increment } => increment.0
+                .checked_mul(supply as u128)
+                .and_then(|increase| increase.checked_add(base_price.0)),
+            PricingMode::ExponentialCurve { base_price, growth_bps } => {
+                let factor = growth_factor(*growth_bps, supply)?;
+                // base_price * factor / CURVE_SCALE without overflowing on large prices
+                (base_price.0 / CURVE_SCALE)
+                    .checked_mul(factor)?
+                    .checked_add((base_price.0 % CURVE_SCALE).checked_mul(factor)? / CURVE_SCALE)
+            }
+        }
+    }
+}
+
+/// Fixed-point scale of exponential curve factors
+const CURVE_SCALE: u128 = 1_000_000_000_000;
+
+/// `(1 + growth_bps / 10000) ^ exponent` scaled by `CURVE_SCALE`, by repeated squaring
+fn growth_factor(growth_bps: u32, mut exponent: u64) -> Option<u128> {
+    let mut base = CURVE_SCALE * (10_000 + growth_bps as u128) / 10_000;
+    let mut factor = CURVE_SCALE;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            factor = factor.checked_mul(base)? / CURVE_SCALE;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base.checked_mul(base)? / CURVE_SCALE;
+        }
+    }
+    Some(factor)
+}
+
+/// Allowlist-only sale phase that runs until the public sale starts
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Presale {
+    /// Block timestamp at which the public sale starts
+    pub ends_at: U64,
+    /// Presale price per payment currency, the public price applies to currencies not listed
+    #[serde(default)]
+    pub prices: HashMap<String, U128>
+}
+
+/// Membership proof for a series Merkle allowlist, see [`merkle`]
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AllowlistProof {
+    pub quota: u32,
+    pub proof: Vec<Base64VecU8>
+}
+
+/// Who besides the token owner may burn tokens of a series
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum BurnPolicy {
+    /// Only the token owner
+    Owner,
+    /// The token owner and accounts approved through `nft_approve`
+    Approved,
+    /// The token owner, approved accounts and the series creator
+    Creator
+}
+
+/// A completed handoff of a series to a new creator
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CreatorTransfer {
+    pub old_creator_id: AccountId,
+    pub new_creator_id: AccountId,
+    /// Block timestamp of the acceptance
+    pub transferred_at: U64
+}
+
+/// Referral activity of an account across all series
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct Referrer {
+    /// Paid mints made with this account as referrer
+    mints: u64,
+    /// Rewards ever credited, by currency
+    earned: HashMap<String, Balance>,
+    /// Rewards not withdrawn yet, by currency
+    claimable: HashMap<String, Balance>
+}
+
+#[ext_contract(ext_self)]
+pub trait ContractResolver {
+    fn on_withdraw_proceeds(
+        &mut self,
+        creator_id: AccountId,
+        ft_token_id: AccountId,
+        proceeds: HashMap<TokenSeriesId, U128>
+    ) -> U128;
+
+    fn on_withdraw_platform_fees(&mut self, ft_token_id: AccountId, amount: U128) -> U128;
+
+    fn on_withdraw_referral_rewards(&mut self, referrer_id: AccountId, ft_token_id: AccountId, amount: U128) -> U128;
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct TokenSeries {
 	metadata: TokenMetadata,
 	creator_id: AccountId,
 	tokens: UnorderedSet<TokenId>,
-    price: Option<Balance>,
-    ft_token_id: Option<AccountId>,
+    /// Accepted payment currencies, `near` or a fungible token contract, and their pricing
+    prices: HashMap<String, PricingMode>,
     is_mintable: bool,
-    royalty: HashMap<AccountId, u32>
+    royalty: HashMap<AccountId, u32>,
+    /// Royalty total at creation, the ceiling for later royalty updates
+    max_royalty: u32,
+    metadata_versions: Vector<TokenMetadata>,
+    metadata_frozen: bool,
+    title_format: TitleFormat,
+    minted: u64,
+    burned: u64,
+    /// Editions burned while re-minting was allowed, reissued by the next mints
+    burned_editions: Vec<u64>,
+    remint_burned: bool,
+    burnable_by: BurnPolicy,
+    transferable: bool,
+    transfer_locked_until: Option<u64>,
+    lock_duration_after_mint: Option<u64>,
+    mint_start: Option<u64>,
+    mint_end: Option<u64>,
+    presale: Option<Presale>,
+    allowlist_root: Option<Vec<u8>>,
+    max_per_account: Option<u32>,
+    /// Share of the price paid to the referrer of a mint, in basis points
+    referral_bps: u32,
+    /// Split of primary sale proceeds in basis points, all to `creator_id` when empty
+    primary_split: HashMap<AccountId, u32>,
+    /// Account invited to take over the series, see `nft_propose_series_creator`
+    pending_creator_id: Option<AccountId>,
+    creator_history: Vector<CreatorTransfer>
+}
+
+impl TokenSeries {
+    /// A new series with every option at its default
+    pub(crate) fn new(
+        token_series_id: &TokenSeriesId,
+        metadata: TokenMetadata,
+        creator_id: AccountId,
+        royalty: HashMap<AccountId, u32>
+    ) -> Self {
+        let max_royalty = royalty.values().sum();
+        TokenSeries {
+            metadata,
+            creator_id,
+            tokens: UnorderedSet::new(
+                StorageKey::TokensBySeriesInner {
+                    token_series: token_series_id.clone(),
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+            prices: HashMap::new(),
+            is_mintable: true,
+            royalty,
+            max_royalty,
+            metadata_versions: Vector::new(
+                StorageKey::MetadataVersionsBySeries {
+                    token_series: token_series_id.clone(),
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+            metadata_frozen: false,
+            title_format: TitleFormat::default(),
+            minted: 0,
+            burned: 0,
+            burned_editions: Vec::new(),
+            remint_burned: false,
+            burnable_by: BurnPolicy::Owner,
+            transferable: true,
+            transfer_locked_until: None,
+            lock_duration_after_mint: None,
+            mint_start: None,
+            mint_end: None,
+            presale: None,
+            allowlist_root: None,
+            max_per_account: None,
+            referral_bps: 0,
+            primary_split: HashMap::new(),
+            pending_creator_id: None,
+            creator_history: Vector::new(
+                StorageKey::CreatorHistoryBySeries {
+                    token_series: token_series_id.clone(),
+                }
+                .try_to_vec()
+                .unwrap(),
+            ),
+        }
+    }
+
+    /// Current public mint price in `currency`, `None` when the series is not sold for it
+    /// or its bonding curve has outgrown the balance range
+    pub(crate) fn current_price(&self, currency: &str) -> Option<Balance> {
+        self.prices.get(currency).and_then(|pricing| pricing.price_at(env::block_timestamp(), self.tokens.len()))
+    }
+
+    /// Current prices of the currencies the series can be bought with right now
+    pub(crate) fn current_prices(&self) -> HashMap<String, U128> {
+        self.prices
+            .keys()
+            .filter_map(|currency| Some((currency.clone(), self.current_price(currency)?.into())))
+            .collect()
+    }
+
+    /// Number of editions counted against `copies`. Burned editions only free up
+    /// supply when they were burned while the creator allowed re-minting.
+    pub(crate) fn issued_supply(&self) -> u64 {
+        if self.remint_burned {
+            self.minted - self.burned_editions.len() as u64
+        } else {
+            self.minted
+        }
+    }
 }
 
 #[near_bindgen]
@@ -57,7 +374,24 @@ pub struct Contract {
     tokens: NonFungibleToken,
     metadata: LazyOption<NFTContractMetadata>,
     token_series_by_id: UnorderedMap<TokenSeriesId, TokenSeries>,
-    accounts: LookupMap<AccountId, Balance>
+    accounts: LookupMap<AccountId, Balance>,
+    proceeds: LookupMap<AccountId, HashMap<String, HashMap<TokenSeriesId, Balance>>>,
+    account_storage_usage: StorageUsage,
+    token_storage_usage: StorageUsage,
+    /// Most storage a paid mint adds besides the token: lock, allowlist count and referrer records
+    sale_storage_usage: StorageUsage,
+    /// Most storage added by crediting proceeds to one account
+    proceeds_storage_usage: StorageUsage,
+    locked_until_by_id: LookupMap<TokenId, u64>,
+    allowlist: LookupMap<(TokenSeriesId, AccountId), u32>,
+    minted_per_account: LookupMap<(TokenSeriesId, AccountId), u32>,
+    /// Share of every primary sale kept by the contract owner, in basis points
+    platform_fee_bps: u32,
+    /// Unclaimed platform fees by currency, `near` or a fungible token contract
+    platform_fees: HashMap<String, Balance>,
+    referrers: LookupMap<AccountId, Referrer>,
+    /// Series left in the first deployed layout by `migrate`, see `migrate_token_series`
+    series_migration: Option<migration::SeriesMigration>
 }
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAALQAAAC0CAIAAACyr5FlAAAAAXNSR0IArs4c6QAAAERlWElmTU0AKgAAAAgAAYdpAAQAAAABAAAAGgAAAAAAA6ABAAMAAAABAAEAAKACAAQAAAABAAAAtKADAAQAAAABAAAAtAAAAABW1ZZ5AAAjJklEQVR4Ae1dCXgcxZWuvuaQRqPLsmXLtuRblmUjGx/Y5jIOJDF8JPtBgGS/xGETskk4svZyBJKQa4HgtU2CSUggHIaQXa5NwocBw2cbAsYXvmQLG99GPmXd19zd+1f3aDTT3TOaU2ppuiLinu7q6lfv/f3qvdevqpiiYSNJJotEJDTP4D+JwT8S/U3PsAw9Z5Z4OCCCa5SHDOUZDhn5t3wmntuTrsMnfWfsG2lviMSiH+iSUlX+Bz/o/8ySCAciXiTKvB6QECLKYMkQQ9lEiOyjrkwiBTUFN8OwDNuLjD5uNS8nzAFwG+wFk8Fqhec4kV6UpEdzUDRQWhVUKINHUPUl3GnzhgQ4IClcppiANgkO2RQraUFJqpqjB7NUVQRJMlGRgHTTVzXIdsiBAiMkl1QekDw4QALooYiQyQliOBVazHvTwQEIIkwoKamQZIeV4BAi98ZUFekQaprbkIVChxroEBSqTRIuiWsO+jRFYyT8MPOGAeAAVSN4LN5mBSYJkJAgONA+g1uSwmECVJlV084BBBBYaokkUuIGB4YyigxqFJtlMHIg6DBAiHGrkHhtDkkxgmnLCcJvMDJyKNIcFBx1eON9wePVHPG2NxTZOsT6FL8o4wAHHU0o3swyRDgQt0BjgQMDCIUEjAxzKBkiuOjpBgQqv++xBRsTHKZb0sPMIfkvfetjdiwWOFjTMYnJuyFwMbaIo4CDhmBjo2oIcMbsAuUAFXSU0SWKKyt/BTaZlw0coNZHlCEiiuaI393JBv4N+T5GEbdGcwQHE3NMGfKICOsghhVF4JEqRE9zxLZSwto0D4cOB/SEHgEOCiDq35hqY+gIPd6eILoO0zRS8mHgwAX6XS1Ss8Tbtllv0HOAip6qht6O9IIDwKG4UIGnt6Z5NNQ5IKeQ0aGjp/SCAydNtdHDliz9FwAIwwbpBQdVGqbayFJUBLstA6B3XAmBQ7Y4sps1Zu9lDkB3BPERBIeZFGoio4cDdJKichwEh/yrV5/01DP/zUIO9JqkMjjCjZAs5IbZZS0HZEhQcEhiKENUW8s8k3UcgMsCSKDbNF0d7ovppmQdBKJ3GGCgHq1EeHmEofCIXtm8km0coPDA/+iwYpoc2Sb8PvurQAIf40yd0SevsrECgAHNYSqObJR9HH2mE1/NYnJAnwOs6afoMybrzwIY0BymzZH1QNBngIQ13cxickCHAwAGG7GMoU4d81SWcgDAMA3SLJV9PN02wREPl7K0jgmOLBV8PN3WTGqK56aBqxMQiSjSREeOlRDcpd+HlBiePCtHlK/igyLLSlw/wl6hCvTguSyW9AZ/QlRJBPQERLpceT9TlbqUBg04/AHi9bHDC6XyUu+0iczcqe5JY7pGDSOOHCoJt5tcaCGHT9n3HLbvOkhOnBFOX2BZVrQIqbMoVgteH8DKlpWIFaO8sypJzSTXpNGukkJis9EQQWc3OdNIDtfnbj9gqzsinTxnaWhhLILIc7HaNM41JtO7JqTeVbx5fj+ZPIbcdHX7lXPEmZNcljy6IjwN0IQ74ooWwf97yP7j1o928a9tcGz/lIUKwduc9gItBYUxt0q8cXHnpbP81eM8xNpDkpYqlng7yO7D9vd3sK+85zxUT3ge+0aknag0N2hocIDJXi8zvFBceq37377SOWaMn+Cd88nIiMEHMF1WGI0XuLc+sK3+q+PIKU4Q6DCUlgKw+nzMxNGB5d/oXHKFe1hJgDYLqsIxoX0SAAqqAqS+nn/2H46162wNLazF0puTp71jwM8YFxwYpH0B5ovz/PcubZpbE6CsV7RF/DyDPDhy4iSz4rmilzdYA2IaDBFoC45lbl7suffW5opyWBN9IVVFLQAKqhiyfQ+3Ym3x+m28wCENT1XJKD85ew50tOEKZICxYNkt7pXLWyoqxL7fS90eyHgqKCRfvtRdWiBu2Wfp9jCpDDGwewoc0iO3t99/W3thIUa7vrRFdKrKRkvXLXSRALf9U5521pD4MCI4wCxBII/8oGX50k7BKstAl8txnhTp2r011b7qsZ6NO+0d3dQKSaL4/ExJofTU/U03LHFTUcqDSRLtBG8JEHTtytnuolz/+7vtgJ0B8WE4cMguH0XGbTe7KR8xlOgWyAf2B/4gaeUPZ/CnO/DLKmTCBHFqmWfjJ7Zud8L6IxAgxfniH+9rvmaRL0k1pu0FqGLJxdX+Qpt/4yd2dNxo44uxwAF2+QMMRpPl3+qkzNSVNABhJT4Xc+a8UH+aO3acqz/FtbRyXW7OwhGLg6Y/6kNKJBPGiSMLxfVb7fA14pcExCbwzOplbddf7aFDSRqL3MGLq/xul7C5VgjGSNLYfmpNGSvO4fUxX5rnW35rO1UGWp0BqfPk/Bn2tQ15mz7hDp60n74g+SAtxMQYUlJAJo/1zq323XJNV+VkP1X7KmzhZ4Dc8GX38dOuh563C7zqclRGAq/3fdOFG3XajHpT3BdABUvQ5X1H+He2CVYhXqrifkDyFQ2kOfA2F+dLa+5pqigP6LygPI12vPSGc/ljzlc32A+c4LvcEssRBJTwh3euy02OnuY/3md9b6uts8Myc7JbQHxMgzDcMq3Cs3GH7WwjF4/xAVNjxkTfqmXNObkIdibP6Fh3SsSaI00o9az7KMfliZzoHuu2jF8zEDi8fnLHDe6vX++ig7qqcKSlhfnZmrxH1joRZETsHKFPlRKGQSfwAIrU2sl8sMvy6TF29uRAYTEi6pFticTuJPlW9q3NVjoA9VU4hnn4h12zarw6VPV1bwLXA2TUKKm9lf9wr2Cc+KlRwAFzfdJosuKuFmdez+JlIdbypLWV3P5IwV/WOywCjVXENhcAGo6T6o5bttba5lT6R5QGtPiYNMa7pTbn2GngLPQYnQOMWZfPFB/8XguGrX4oE0b53t2a29SWkZBuEvTH5E0S7SV7C6yNW65uHz1GDnaFN8KQgIf86k95f/9njs2SwHhst0o7P+OWrcptugCTJLxFaosIueTW69qkmIlweBgqoBoqq82XyPbS80si6D6YAFakp8GUWzEEOGBtDC8ki+YGqBRVAODI/7zlfP5NJ4QdW2FoWZFjk7bW2VauLVS3iar4LDI9MLXCj0C49kblDC6hAqqpFU+0G1I8j45zlAlgBRhihGIIcOCLdsVI34xJHvW4zpHzZ7knXrX54U0mVSy89NJ66+79MEYi78cYX+abPsGL4SxawSVUQLVU413RHqA97yNgQvlIHxiivdj/ZwwBDqiEaeOJFSEK1RsjkNc2Og5+LvDJ5kGj5eYO5oV1TrXtiWgIT2ZWErs1qhhwCRVQTUfxZEhQImXCtPEJ68gMkWMIcMD7mFPlViODJZ52ZtMO3u1NiVmY1rdlH3/urEZ5+MnMKV0Ou75WgqrCJVTQcaozJAqlWZoG4AFDMvqQOBs3BDjwfiNzR/2CMqShhT940ppiXAie4YlzwpFTgtosRcC0zAsjV1cOOIlLE8u8asjGydekq0mUFYlaV0k/LfaNhgAHQhTI6dKCo62dnL5AY1ypFHi2Le3S+QYNBkSClC184VM/V3kYPBqBDCvUCaOlQkzf90qUFQb5CGcIcMAgcMBd1JSOTqR0aM4mfoJaHu06YmZ5Ykc+X5SCS6jQ/4WyIqoh1K/kGAMc0bqssk+jVevzPIP8Ux1w4D6exeJ5+sUYAtKnrX/OGgMcEs0Q1pbc3IGMFbq9UUYcLaFpPUNZEQ2waX1Qn40ZAhxgRUOLDqmOPDI8nyCXYkAKlE0rBqN+VyBghTGwQT+ND3xBQPDIKbuaFho2DUwZ6/X6+10+gARDPF7m+Gle7eNkmlssZYUZIe1lMwKCew/rgMNRKCI/A3OXBuRNQvbQ/mOO/gcHWGFGSHvBgVADZiKJHo0OD5Cbr+muGOUPBPpbeUBzdLnIjjr5uf32cIYyAazQj730MqyfjgwxrMBjOHaaP3jMqsw36e26n1RO9i1d4sUUyN6T/XWEYEPtEb7xPFKJ+uuRAgETwIroLlR/USI/p9/6HatXHEdON3If7JKjXSoYBMjtN7cuWdDl9qguxGowLdeQO1J71LLn0/4yO+T+gQlgBRhihGIIcIARHCu+vsHZ3KBMQw7jjERy86WH7+icOTmArPGwCxk/xMji84v/+55T0o53mXg4InUNLJgAVmSi+STaNAo4kPaHea3rPtSYpeiTD1nj/icfaF04w+3xMZiN2G/FKpB1H/M799v6wyxlCboPJmR68nf83DMKOEAxPkWufCnv888Zde4FrvnJ9CrPy79pufNGt8MmIVdKnt4efzeTr9nlYh56zuF1yXNkkm+mrzt5go6j+wb5HquQa5QcUlADNX6hhe3otFx9qVsnZ1MkObnkC5e4rpzp5jne48UCB3x7N0EUBFNqkSMe48/jYq6e57qkxq+NLj3zd0djGxPjQxeoOnGWLytmaqoz9oUW0X2R+enjRe/vEpAjbZxiJFowDV2QMON5XnXO0hv0EinkUOmsGf5ZFzUhPwNf4c81SK1txNPXRCPg5qrZPi0y4hEDcBOQpF89mzulomv+nIA6Vy2eJvqsg1TI13PQcXS/z7r9WcFY4KCSEKUHn8obNcxz9SI/lYSWXTIUSkv9pWXyERCjraNiIQxZGHnJ2nnIUMfKMHetLFr7i+aqKiwio2o9hZ8gTCDvbeLR5bQsApACKTq3GsjmUKiDJFo72e8/WvzuJjk9J5qDAmDAicAfDoCP2H+okywyFKpgJB48KXz7l8V1n3LqYIwOV+M7ha5xBN1EZ9Hl2JMk4msxzbUMZHOEeob0HMyFx4xnrJtQjYmNcPpTE22oZe1BnzZH6BbEHs43c5v3WKvKPWPHyjNy+9RYoZu1B7LKfnmdfdljBY1tXPxzM7UtZe6MEcGB3uI1QlTj3W120ctXjvPQNURSkUR0/sUPDkoVxQe7fmsuJsxVjvfwWOcpCdTKQ0ljE7PmL4U/eyqv08VgCShjFoOCA8yC/sDHyQ92W97fYXXa2IljfByytjAMphUlCYFDwUe3m6zfat1ZZx0/QiwdHmAt8iehPqkCJgACjnjdzN/ecyxbmf/qJrqqHLpp2GJccIBlcCPxleFsE4d5rVtrc2xsIC+HceaLBPJQSp8i6akY7d9EwYF2FL/3yGn+/zbZDh6z5XKiM1fMKcCWaHoogewxLMoEnzojbNhseeD3xX94zX6micO0XnTQyMW4a4KpuEaXWpDYygo/JhrVVJJZU7qQO44M4VTSPPHxc8GtIw6eTNIYBDK9XmIRWJA0Y5J/dpU0fXznuDJ/AQZBWR+IfurmHD1t2fVZ7p6DZN9Ry8ETPGFEQwUzVHwO/zlowAGiIQyfj67uYrdiUolos4oWntisSE9P/gU8eZbF7P5UChCGQBxGwFw7ybUFMKEBsgdVOO/24BL+n+10sS4PXQEAGe3J05oKlUndO5jAEeqgku5AhxR5WJH/L3QxsYM0OpCgKpykIAjoLou0GHwE0eWaUQ1lXWJ7TiqMpkw30msIqoxGUg/DkvzXwLZykj0yb0sbB0xwpI2VQ68hExxDT6Zp65EJjrSxcug1ZIJj6Mk0bT0alN4Keq84jVrXMejIpMmLwULbSZcgCaYrmzQHk74RPM+xiXbEwQRildeMRG4YcgfdXqbTxXe7JZ6XECJLpeD28pHJo8MfkMKDYPgiM+iCYKnxLxXeJ3svEoxzbMydX3MvrOmeVOYuKQpG0KUAaWklR84Iuw/l7ayTdh2yfPY5j0zu5Jb3gM4AMj5+7nwqwavBHj4ffOCAusZ3lrZOctlMN/2g1bOiLRbvKh5Oikt98+Y2I/fnZD2/o9byzBt52/azfpFJLmGCxrVSGKE4gZSWktJR3oXzvUhKwoe3HbXcc2/mb97LYd0R4394M/RXWV3lAmnhQ8bWOuHcBctVc9x8+NI8sEEwDtBPdKSgQKya7Pvaou7yUuZwPdvQzGFh6/gFjVj4sALpu19NeQWmMJLwPXnqJP8Ni7onj5FOnGHPNtGZbamAT5c/aTxpaHBAQopJqOIgfuK7+e5D/PkL1sVzXBH4CPEGKBEJLs2o9ly7wN3ebq09wgE0qqZC1VUHaQNHeLsySRxPqqZ4r7/c5bBw2+oELOhu2JQO44IDtgUQcOOigNvH0PXOI2cIQsb4231IaGoWFs/FbhrRk7ICxFkgXT3PbefJ5r0CJrArCRnhUtMeZwQcocdgmoWDXDrLPbbE/3GtBd9s0/j9L/SQ1A8MCg4oYywtfddNnavvaZ4yRtq819LSoV6nXMaHBHx43NyCmR6abEddW72Cjdl4af5FHlYSsa2CCF3e1wCTWXCARtkNqq70Tx3j3bTTBnwYUH8YERwQMTaF/NHN7b/4QQekWDHON2ui+OFOW2M7jLgI2Sv42LIP+sOy+GI3p2zeGVGl5wcaZcn8Gh92W/hgN12ZNjY+YoEDgUOQgSZwEOMPdWAPAbKoKUOhh5SwfyWC/aOqyjzIlnV541JpYTdn/NCI+RzInfnCbN/a/2pyOkVqXaII5J9bc3/4G+fn5+m+GaqCASggsku/5Pnve5ro4vnRJIHbsPCtl12+oviFtwVMole1E/4Ttk5lubjleY0ry5LaOm7jJ5Y+3R8rTwrySelwbDLqKx0pT7aLllXEk7Wv5y7/Xb4IbdmXSgsnMtPHGk5n+oF9tQ9JjyjCDoxtvcjALT5y+fyup34ife8h54mzjDWUQyq3BoYyrPjCOxaWLVq5vFmwyTt66j4I60fbxQdva9paV3y4HnMPY+FDtwGogY2fCMtWF1ntfdwL3YNS6KDLus+fEfjmkrZZ1X6qReR5exGNB8jXv9K9bb/thbet1kR2hohoJAM/DDes+Pzs/UvbrlvsDuqMUJ8DZEy5r2qs9HGttbmNzhIIL/L4QnZ9xjc0Wa6aG+nfhtfDsUgchZKD597ZYo0xskQdVliycz+/YZc910YjsLH+BKSXIqlRwmwGEPb39+0XGun+UXSahUa3wYWZPt79zpbcVgykhlEexgIHps/PmOh9+PaOnBzNljyQa4BUlPsunizuqLOeb9bxX6BCqH/bEN2/VYBChwzvR3tyT56Luk1kDHDsqOXf3W6PMzYPSQPH+PP4mS37ha21wsxJ4ogRmm06RJJfSByC9OZmm3E8F2N9lcWY4sxBji72blS97z0/fWTe7O4n7muvKCWenthozzVqY2IJg7VvW+/7bZHPq7eUg1JVwiaS5DvXt4ti/3UfwMUi7ptrbT94uODocV5nTqVIrr3MNbdKpOvpGqMYS3Moi9ifa2Qun+mx0ik/ekwKkLIyeXyJ4t8i7LjrkOB28wtr3FH9W5bk2ch726wNrWoPWXlkujSHqgOwpk81sPXnmCWXeCyRlhNq2p1SV6f4zpacZIwh1ZPS8dNY4ECPoFR3HrR+foZdPMdjxSrgmuGZ9lokFeN9MyeK/9xpw45ouv7t1v1CY5Nl8Ww5PqYFmUicReIne4U9h+F36DAyQ+DAk4BXbAVht3ALZ2t2EeFIroDljayYI2mEsEf/6VUdCUQ5BYv91Y2Ou1c7O1rliIJuNQ+ZP7v7yR+3jxups3g+xheWlZ5907Z8dRFdaU6vlwxHZk/Dd/8BWOQUtK19y3LwkECjIOHFRzAFd3yZH2G68NMDdazHtoGipee5EC3Mjr+sz7vzN/mdWGBaxcGealgn4zL4tz9tGztc0tofGON5ToRzeN+qYp8uPgKkZnJHtM14Qg/JxAE2rzxxhn/53RwaTAsv+GJgJbOwPZQhsKH7ToWTO3DHVkGE/vjx75ydbbHwMX8u7NOOcdHt0+fWWe5+rAhrVaslESATRvlp0KzfC0QPZ2zbfqGzhVVLQCQXTXIZZGUwI2oORVhUf1jFtW87/v2hgqamWOPLlfO7nvl5a1UFFpJTyxmNIG3i+XXW/1xZ5O6OHF+wAXYBDUXom73qltL8GzsTfva5paFFswKuSCaOxoeWND8uueaMQUV02hHk/tsHOctXFnTEGF98ZO7FrjX3tlWMjOrfvvCW7f7Hi/wq/5aTJ7VGf3rmriDy0dhGOjt0noCtQ40xqqiVmg6t/XYKDgLiHNqCHWVffz/3nsec7RhfooHZQ+bNcj1xL41/0Pn4kUXWH+Izb9p++acCD1a6DWtkAPcnwOebrq5IQuVfNvjwxkBHGJ906Oy/UxBSoZPBS4NJ9Nqnwv548Z28H63I78CrFs0+9ZErFnQ9/dO2ctgfmjXdKD5Y8fFX7PetLvIBH/JnVeR4oqbO82TpwGfQUgJgpXEkYnTZr/dYHUoyf0qXusw/VvMECGJ4Afn9Pc3TJ/h19+sGPl7ZAP82P5Z/6yXz53T/4b62ilH6+kPxb5etLqb+LUcuNMvbe2mIUU64EIbQKCGAo8iZnl0NBI44HDrP7uweGDNIS4pRwAHK4NzXTA28+MumaeP8WMZaRSu1Ty3iS+sddzyaT+3TaPqD+rfdT/+kbewIHcEr/u2Lb1vuXVUk+ciR0zaXh0XLOgULn/vo0ivhY5BSbcRwBkouxfEI212XlZB8pwYHDDnTqD+86hCZ4VMGAgd6Cp0xbmLg2QcpPnT1h0X2b+96tKCjL/92zb3t5dHt0+fX2R5YU/jR7txujz4HABhMgcGiPGpwBOBN+CpKfTH2OY9HZEB/ZblneKFmUWWGHK7PhfllhKLPmoGkzE+qKgN//XXT9Il+GpyILHjLMZHpjc0534d/2xjLv120oOu5B2P4t+Izb9jXvEZTx3Q1B05ictTuz3LVKspPkLkzf7pfXmsykri4f0H2NguzaI7f6tSkJrHYIMBq7tQUnZd+MmFi4OkHGqeNC+jrD172b1flx/Zv51zselz2X3TtU2RaYGJcdCKIyyPtxqZJMDtUEJXIt65tL8qTkn6/MY+mcqzvxquwbW7k85Go1snUHWOSbjmyuVR/GU9zKD3ykWlV4jMPNk4oC2jtD1Sxwb/d5LhndR/+7SUXI35K4x+6/i1MkBgF3/OwxNuZ05p9zv1kZrX/X7/oSXpnQp5h7viae8TIgDorTCC1h60nzwpmhDSGXORLPlJdFVj7i8ZpFTr4gFitFvHF9Xk/ejSfhpKi26dXLOim319G6MTHYlOAZeoPnOC379MEMXEbQ+5e2nLJNHei+wNBJbg8zLeva//6knY1MtClANm0ncPWoQaJkBrlkz1c2WEFzC1f7MpziL3xbBEDvLSw2r2l1nauSWdzK7xhtUet5xuZK5D/YddY/orwkV841lddIX6814ZNy+PPs4KwkPHb2mG98coupPFFFMzXzSPI6dp1wFrfwOp+9I+o3/MDRu5XL+9esawd81bU2QgsOXWK+8mTRe1dJjh6+KX8qw8OXBNJyQjpyovcH+2xYbVabeoGPp3sPmQ9foq7vMatw3Gl9QApr0D+RwD5HxQfqm+hkZSE/wKS6s+xmL1YVelVv+giGVEaWDTTU3+O5mfgQ1qM1x0KA94NQrO3Xd/x6H90FBbqpRlz5Lcv5r/xkRBnAmI4nRk6NrbmUDotkqISacE018d7bOea9fQHh20crSdPc9fMdVtyNG+k0gjVH/6pY6VPDlhb2hP4Jo6dfo6fYf/lMpdd+64jqlssXnuJBwmlmPuK9e1h2QAiIVMG5i6204a2wGz/qeX+n3+3e/nSjly0o01At5Cde7gHnsz3+cwEYw3Uo2qOHnyUDJfmTnF/sMve2MqqXn3of+TVfXrcgtXmL62Jnl8okXGjfDv22+uOJTC9DMrjbCOXa+Xnz9QLiYhEsBDkdF01y5OfSxD07HbzHd0MPgJgL1zE10YPZxdMd3/nes+vv9+6YK6b2v/a3DbsEtHF3b2quPYotk/QsGbgThhlUhO0bmU5+7dVDaNKfTovlsIggdTVcbf+uvjTEzySdbVMQ7jzpqs61/y4LS9fnmsfXoMnoo/86knnmtcc8EF1Yxvh1cOPMShgkeQn7mm56VoXpU3nybJFzJGuFvZ8CwcDmX5RY0heHsnPI6XFfiFPol6rVmHgMYC2RH75x8JVf7VjwRn8Mk4ZDMNKiFvY3b5Uml/l3r7fhrdZpT9QC8zdB/v0AnP5rEj9wZP2dvahpx1rXqXRiRjGQehR4QdAEubCY74u5rVi9qK+jKEPAsRilQoLMPNAHDNaHFMmDi8R8/NEagIjWKILKVxiydOv2h56Pg+DUUKQDacwQ8eDChzggYyPKy5yfbhbzz4FPjikntvqlfxk2B94E3ly4KDlzkcLX1pvx+iTnACAJ8x13rTLXjXaM2G8PDDoChsn8YfroT/daoowYRdL5OlXbA/8qRCKM1HIZggQ4c0ONnDI+CgaJi2odm/dG8u/bWhirqjxtHSwz7yWs+y3hfvocJ4kMoKipPig+wMV2qUZU/xYSEjHeghnbexjC+nuZB/+cwF0BpARv4Mdu9X0XjWQzTG1nP3HYw0jRka3OcK7LpDjh9lv/nzYvqM8tEX4FeUYXsbC6a72Lg6ODDKN0/VeIkOHY5mbF3vuvbW5olyelCvrES0B+megyTCUMGT7Hm7F2uL123jBwLuuGAgcE0ezf/5Z84gSf5xvJI+95g/wt68obGjt9R7DRQIfEgN57Nn04fXjPIZjBYdz4ujA8m90LrnCPaxEtjNhb+pANKxJYAL5qgFSX88/+w/H2nW2hhbWYjGWBRpGLj00CjjgEVgEZlQJA28w/gJ90NAitbSnNF7E/7jwmsjngBbB7MUbF3deOstfPc5DlNVBAJFwlEBVyNrC20F2H7a/v4N95T3noXo6tSkUDglv1lDHRgEHmAKWImSUaAE+BpDL8q7pbFmJWDHKh/kmNZNck0a7sH8UzQOVsGc2zdxBfsaOA7a6I9KJc0g3Z5CSoorzJtrlfqtvIHD0W5/T/iCoEFFeagxpiAArNIX8H8UHNCKGIeRn4ABXjWl4RmOIkQJy0Wg0/HmIPPSRHSCgo0rYyALnWddkNny31ElwxifYpLD/OAAb2iwmB/Q5YIJDny/mWXCART6LyQiTA1oOGG1pQy2F5pkB40BPLHfACDAfbGQO0HwUI9Nn0jZgHAAwTIN0wLhv/AcDHKZBanwxDQiFEmtCY0AYb/yHAhjQHAOwnJ7xWZPlFMoqAwYpnRGMY9MuzXI8hHcfxqgEYNBviAiDmT5LOG+y/JhCQ1YX1Fuh6sOMk2Y5IsK6T6duyDkysisrjzBhV83DrOeADAkZHEF31jQ7sh4TlAG9DkoQHLJNaioQExzgAHKeg0gIgkP2VkxwmOCg4Ai5riFw4Czmd5sjS1bjQwZALwZ6wQHAmD5LVkNDBkD48NELDtgh9IKpPLIWIAw1RXvNUTl83sMMqBOaSh8OnZ5L5r9ZwAEqeoqO3q72ag6cg9aQwRN2vbemeTSkOQBzQxMojwBHsPeYhWOWbOOAntA14IDWoH+m8sgmdNAhI2JAUTqvAYdy2rQ8sgkb8nc2nQ5HBUcoTKZzk3lqCHGACjqKLogyV1b2aoYQB8yuROUAzeeJYkRE0RxyU3o2StRnmBcGIwdiizgWOIAn028ZjCKPk+bIoIbOTTHBoegb2kYUvaPToHlqMHAAAqXDSR+CjQWOYC+pApFbGgy9NmnsmwNxCzQOcMhPi2LP9k2JWcNoHIhflPGCg2aZygaI+VnfaMKOkx5FcHQMiBsdUVxZ7QODbZsmqpY1g+MMhQSQQa1H+l88JV7NEWyLDldYlFXWIfE0b9YxCgcQ6RLjRkWQ6Lg1R6iTiueiwCNeCIZuNg/6nQNBSUWNdMUgKEHNEWoJz4I3FHpw6Lx5YBAOyO8wtTDwAif7DicLDnloofjAgWyqmoaqYVBBc3J6hJKSBZA8OBRehHApI0TWJDJmDcKpLCIjyHYFFXKAK+XOpwqOEERwIA9rlDiKEWiSVDRayh3LjgZkJstfSWWuY8oJHUKSHUbUPEvcIFW30Ps7XG/IEJEwTRu+Tbpo7X2SeSQP6yw8EPomhnR1eHZwGniUHs2hJUQebhRaFbNVTl6lYyHdrNVc31LLsRhnFI4pfJSNiCBLKS56kRGjgSQv/T8xs3pmiB4WBQAAAABJRU5ErkJggg==";
@@ -74,6 +408,14 @@ enum StorageKey {
     TokenSeriesById,
     TokensBySeriesInner { token_series: String },
     TokensPerOwner { account_hash: Vec<u8> },
+    Proceeds,
+    MetadataVersionsBySeries { token_series: String },
+    LockedUntilById,
+    Allowlist,
+    MintedPerAccount,
+    Referrers,
+    CreatorHistoryBySeries { token_series: String },
+    MigratedTokenSeriesById,
 }
 
 #[near_bindgen]
@@ -100,7 +442,7 @@ impl Contract {
     pub fn new(owner_id: AccountId, metadata: NFTContractMetadata) -> Self {
         require!(!env::state_exists(), "Already initialized");
         metadata.assert_valid();
-        Self {
+        let mut this = Self {
             tokens: NonFungibleToken::new(
                 StorageKey::NonFungibleToken,
                 owner_id,
@@ -110,67 +452,168 @@ impl Contract {
             ),
             metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
             token_series_by_id: UnorderedMap::new(StorageKey::TokenSeriesById),
-            accounts: LookupMap::new(StorageKey::Accounts)
-        }
+            accounts: LookupMap::new(StorageKey::Accounts),
+            proceeds: LookupMap::new(StorageKey::Proceeds),
+            account_storage_usage: 0,
+            token_storage_usage: 0,
+            sale_storage_usage: 0,
+            proceeds_storage_usage: 0,
+            locked_until_by_id: LookupMap::new(StorageKey::LockedUntilById),
+            allowlist: LookupMap::new(StorageKey::Allowlist),
+            minted_per_account: LookupMap::new(StorageKey::MintedPerAccount),
+            platform_fee_bps: 0,
+            platform_fees: HashMap::new(),
+            referrers: LookupMap::new(StorageKey::Referrers),
+            series_migration: None
+        };
+        this.measure_account_storage_usage();
+        this.measure_token_storage_usage();
+        this.measure_sale_storage_usage();
+        this
     }
 
+    /// Upgrades state written by the first deployed contract version. Its series are moved over
+    /// afterwards by `migrate_token_series`, and read as not existing until then.
     #[init(ignore_state)]
     pub fn migrate() -> Self {
-        let prev: Contract = env::state_read().expect("ERR_NOT_INITIALIZED");
+        let prev: migration::OldContract = env::state_read().expect("ERR_NOT_INITIALIZED");
         assert_eq!(
             env::predecessor_account_id(),
             prev.tokens.owner_id,
             "Only owner"
         );
 
-        let this = Contract {
+        let mut this = Contract {
             tokens: prev.tokens,
             metadata: prev.metadata,
-            token_series_by_id: prev.token_series_by_id,
-            accounts: prev.accounts
+            token_series_by_id: UnorderedMap::new(StorageKey::MigratedTokenSeriesById),
+            accounts: prev.accounts,
+            proceeds: LookupMap::new(StorageKey::Proceeds),
+            account_storage_usage: 0,
+            token_storage_usage: 0,
+            sale_storage_usage: 0,
+            proceeds_storage_usage: 0,
+            locked_until_by_id: LookupMap::new(StorageKey::LockedUntilById),
+            allowlist: LookupMap::new(StorageKey::Allowlist),
+            minted_per_account: LookupMap::new(StorageKey::MintedPerAccount),
+            platform_fee_bps: 0,
+            platform_fees: HashMap::new(),
+            referrers: LookupMap::new(StorageKey::Referrers),
+            series_migration: Some(migration::SeriesMigration::new(prev.token_series_by_id))
         };
-
+        this.measure_account_storage_usage();
+        this.measure_token_storage_usage();
+        this.measure_sale_storage_usage();
         this
     }
 
+    /// Moves the series left by `migrate` into the current layout, `limit` series or tokens at a time.
+    /// Call it until it returns `true`.
     #[payable]
-    pub fn storage_deposit(&mut self) {
-        let sender_id = env::predecessor_account_id();
-        let mut deposit = self.accounts.get(&sender_id).unwrap_or(0);
-        deposit += env::attached_deposit();
-        self.accounts.insert(&sender_id, &deposit);
+    pub fn migrate_token_series(&mut self, limit: u64) -> bool {
+        assert_one_yocto();
+        assert_eq!(env::predecessor_account_id(), self.tokens.owner_id, "Only owner");
+        self.internal_migrate_token_series(limit)
     }
 
     #[payable]
     pub fn nft_mint(
         &mut self, 
         token_series_id: TokenSeriesId, 
-        receiver_id: AccountId
+        receiver_id: AccountId,
+        allowlist_proof: Option<AllowlistProof>,
+        referrer_id: Option<AccountId>
     ) {
         let sender_id = env::predecessor_account_id();
-        let mut deposit = self.accounts.get(&sender_id).unwrap_or(0);
-        deposit += env::attached_deposit();
-        self.accounts.insert(&sender_id, &deposit);
-        let token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
-        let mut token_id: TokenId = "".to_string();
-        if env::predecessor_account_id() != token_series.creator_id {
-            token_id = self.internal_mint_with_near(token_series_id, receiver_id.clone());
+        self.internal_storage_deposit(&sender_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
+        let token_id = if sender_id != token_series.creator_id {
+            self.internal_mint_with_near(&token_series_id, &mut token_series, receiver_id.clone(), allowlist_proof.as_ref(), referrer_id.as_ref())
         } else {
-            token_id = self.internal_nft_mint_series(sender_id, token_series_id, receiver_id.clone());
-        }
+            self.internal_mint_edition(&token_series_id, &mut token_series, receiver_id.clone(), &sender_id)
+        };
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_storage_charge(&sender_id, initial_storage_usage);
         NftMint { owner_id: &receiver_id, token_ids: &[&token_id], memo: None }.emit();
     }
 
     #[payable]
-    pub fn nft_burn(&mut self, token_id: TokenId) {
+    pub fn nft_batch_mint(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        receivers: Vec<AccountId>,
+        allowlist_proof: Option<AllowlistProof>,
+        referrer_id: Option<AccountId>
+    ) -> Vec<TokenId> {
+        assert!(!receivers.is_empty(), " receivers is empty");
+        assert!(
+            receivers.len() as u64 <= MAX_BATCH_MINT_SIZE,
+            " exceeds max batch size : {}",
+            MAX_BATCH_MINT_SIZE
+        );
+        let sender_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&sender_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect(" Token series not exist");
+        let is_creator = sender_id == token_series.creator_id;
+
+        let mut minted_token_ids: Vec<TokenId> = Vec::new();
+        let mut token_ids_per_owner: Vec<(AccountId, Vec<TokenId>)> = Vec::new();
+        for receiver_id in receivers {
+            let token_id = if is_creator {
+                self.internal_mint_edition(&token_series_id, &mut token_series, receiver_id.clone(), &sender_id)
+            } else {
+                self.internal_mint_with_near(&token_series_id, &mut token_series, receiver_id.clone(), allowlist_proof.as_ref(), referrer_id.as_ref())
+            };
+            minted_token_ids.push(token_id.clone());
+            match token_ids_per_owner.iter_mut().find(|(owner_id, _)| *owner_id == receiver_id) {
+                Some((_, token_ids)) => token_ids.push(token_id),
+                None => token_ids_per_owner.push((receiver_id, vec![token_id])),
+            }
+        }
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_storage_charge(&sender_id, initial_storage_usage);
+
+        let token_id_refs: Vec<Vec<&str>> = token_ids_per_owner
+            .iter()
+            .map(|(_, token_ids)| token_ids.iter().map(|token_id| token_id.as_str()).collect())
+            .collect();
+        let mint_events: Vec<NftMint> = token_ids_per_owner
+            .iter()
+            .zip(token_id_refs.iter())
+            .map(|((owner_id, _), token_ids)| NftMint { owner_id, token_ids, memo: None })
+            .collect();
+        NftMint::emit_many(&mint_events);
+
+        minted_token_ids
+    }
+
+    #[payable]
+    pub fn nft_burn(&mut self, token_id: TokenId, approval_id: Option<u64>, memo: Option<String>) {
         assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
 
-        let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap();
-        assert_eq!(
-            owner_id,
-            env::predecessor_account_id(),
-            "Token owner only"
-        );
+        let sender_id = env::predecessor_account_id();
+        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("Token not found");
+        let token_series_id = token_id.split(TOKEN_DELIMETER).next().unwrap().to_string();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+
+        let authorized_id = if sender_id != owner_id {
+            let actual_approval_id = self.tokens.approvals_by_id
+                .as_ref()
+                .and_then(|by_id| by_id.get(&token_id))
+                .and_then(|approvals| approvals.get(&sender_id).copied());
+            let is_approved = token_series.burnable_by != BurnPolicy::Owner
+                && actual_approval_id.is_some()
+                && (approval_id.is_none() || actual_approval_id == approval_id);
+            let is_creator = token_series.burnable_by == BurnPolicy::Creator
+                && sender_id == token_series.creator_id;
+            assert!(is_approved || is_creator, "Unauthorized to burn");
+            Some(&sender_id)
+        } else {
+            None
+        };
 
         if let Some(next_approval_id_by_id) = &mut self.tokens.next_approval_id_by_id {
             next_approval_id_by_id.remove(&token_id);
@@ -191,14 +634,30 @@ impl Contract {
         }
 
         self.tokens.owner_by_id.remove(&token_id);
+        self.locked_until_by_id.remove(&token_id);
+
+        token_series.tokens.remove(&token_id);
+        token_series.burned += 1;
+        if token_series.remint_burned {
+            token_series.burned_editions.push(edition_of(&token_id));
+            if let Some(copies) = token_series.metadata.copies {
+                token_series.is_mintable = token_series.issued_supply() < copies;
+            }
+        }
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+
+        let freed_storage = initial_storage_usage.saturating_sub(env::storage_usage());
+        let refund = Balance::from(freed_storage) * env::storage_byte_cost();
+        if self.accounts.contains_key(&owner_id) {
+            self.internal_storage_deposit(&owner_id, refund);
+        } else if refund > 0 {
+            Promise::new(owner_id.clone()).transfer(refund);
+        }
 
-        NftBurn {owner_id: &owner_id, token_ids: &[&token_id], authorized_id: None, memo: None}.emit()
+        NftBurn {owner_id: &owner_id, token_ids: &[&token_id], authorized_id, memo: memo.as_deref()}.emit()
     }
 }
 
-near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
-near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
-near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
 
 #[near_bindgen]
 impl NonFungibleTokenMetadataProvider for Contract {
@@ -206,3 +665,340 @@ impl NonFungibleTokenMetadataProvider for Contract {
         self.metadata.get().unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn dutch_auction(step_interval: Option<u64>) -> PricingMode {
+        PricingMode::DutchAuction {
+            start_price: U128(1_000),
+            floor_price: U128(100),
+            start_at: U64(1_000),
+            duration: U64(1_000),
+            step_interval: step_interval.map(U64),
+        }
+    }
+
+    #[test]
+    fn dutch_auction_decays_linearly_to_floor() {
+        let pricing = dutch_auction(None);
+        assert_eq!(pricing.price_at(0, 0), Some(1_000));
+        assert_eq!(pricing.price_at(1_000, 0), Some(1_000));
+        assert_eq!(pricing.price_at(1_500, 0), Some(550));
+        assert_eq!(pricing.price_at(1_999, 0), Some(101));
+        assert_eq!(pricing.price_at(2_000, 0), Some(100));
+        assert_eq!(pricing.price_at(u64::MAX, 0), Some(100));
+    }
+
+    #[test]
+    fn dutch_auction_steps_down_once_per_interval() {
+        let pricing = dutch_auction(Some(300));
+        assert_eq!(pricing.price_at(1_299, 0), Some(1_000));
+        assert_eq!(pricing.price_at(1_300, 0), Some(730));
+        assert_eq!(pricing.price_at(1_500, 0), Some(730));
+        assert_eq!(pricing.price_at(1_999, 0), Some(190));
+        // the floor is reached at the end even between steps
+        assert_eq!(pricing.price_at(2_000, 0), Some(100));
+    }
+
+    #[test]
+    fn linear_curve_adds_increment_per_token() {
+        let pricing = PricingMode::LinearCurve { base_price: U128(100), increment: U128(10) };
+        assert_eq!(pricing.price_at(0, 0), Some(100));
+        assert_eq!(pricing.price_at(0, 5), Some(150));
+
+        let pricing = PricingMode::LinearCurve { base_price: U128(1), increment: U128(u128::MAX) };
+        assert_eq!(pricing.price_at(0, 1), None);
+    }
+
+    #[test]
+    fn exponential_curve_compounds_growth() {
+        let pricing = PricingMode::ExponentialCurve { base_price: U128(ONE_NEAR), growth_bps: 1_000 };
+        assert_eq!(pricing.price_at(0, 0), Some(ONE_NEAR));
+        assert_eq!(pricing.price_at(0, 1), Some(ONE_NEAR / 10 * 11));
+        assert_eq!(pricing.price_at(0, 2), Some(ONE_NEAR / 100 * 121));
+        assert_eq!(pricing.price_at(0, 10_000), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "growth_bps exceeds")]
+    fn exponential_curve_growth_is_bounded() {
+        PricingMode::ExponentialCurve { base_price: U128(1), growth_bps: MAX_CURVE_GROWTH_BPS + 1 }.assert_valid(None);
+    }
+
+    #[test]
+    fn payout_rounding_dust_goes_to_owner() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let metadata = TokenMetadata {
+            title: Some("Sunset".to_string()),
+            ..token_metadata_overrides(None)
+        };
+        let royalty: HashMap<AccountId, u32> = vec![(accounts(1), 1_000), (accounts(2), 333), (accounts(3), 500)].into_iter().collect();
+        contract.token_series_by_id.insert(
+            &token_series_id,
+            &TokenSeries::new(&token_series_id, metadata, accounts(0), royalty),
+        );
+        let token_id = format!("{}{}1", token_series_id, TOKEN_DELIMETER);
+
+        let payout = contract.internal_nft_payout(&token_id, &accounts(3), 1_003, None).payout;
+        assert_eq!(payout[&accounts(1)], U128(100));
+        assert_eq!(payout[&accounts(2)], U128(33));
+        // the owner's own royalty share is part of the remainder
+        assert_eq!(payout[&accounts(3)], U128(870));
+        assert_eq!(payout.values().map(|amount| amount.0).sum::<Balance>(), 1_003);
+
+        let payout = contract.internal_nft_payout(&token_id, &accounts(4), 1, None).payout;
+        assert_eq!(payout.len(), 1);
+        assert_eq!(payout[&accounts(4)], U128(1));
+    }
+
+    fn sunset_series(token_series_id: &TokenSeriesId, creator_id: AccountId) -> TokenSeries {
+        let metadata = TokenMetadata {
+            title: Some("Sunset".to_string()),
+            ..token_metadata_overrides(None)
+        };
+        TokenSeries::new(token_series_id, metadata, creator_id, HashMap::new())
+    }
+
+    #[test]
+    fn storage_charge_bills_growth_and_refunds_freed_storage() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let deposit = contract.internal_min_storage_balance() + ONE_NEAR;
+        contract.internal_storage_deposit(&accounts(1), deposit);
+        let token_series_id: TokenSeriesId = "1".to_string();
+
+        let initial_storage_usage = env::storage_usage();
+        contract.token_series_by_id.insert(&token_series_id, &sunset_series(&token_series_id, accounts(1)));
+        let cost = Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        contract.internal_storage_charge(&accounts(1), initial_storage_usage);
+        assert!(cost > 0);
+        assert_eq!(contract.accounts.get(&accounts(1)), Some(deposit - cost));
+
+        let initial_storage_usage = env::storage_usage();
+        contract.token_series_by_id.remove(&token_series_id);
+        contract.internal_storage_charge(&accounts(1), initial_storage_usage);
+        assert_eq!(contract.accounts.get(&accounts(1)), Some(deposit));
+    }
+
+    #[test]
+    fn storage_charge_skips_unregistered_accounts_unless_storage_grows() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let token_series_id: TokenSeriesId = "1".to_string();
+        contract.token_series_by_id.insert(&token_series_id, &sunset_series(&token_series_id, accounts(1)));
+
+        contract.internal_storage_charge(&accounts(1), env::storage_usage());
+        let initial_storage_usage = env::storage_usage();
+        contract.token_series_by_id.remove(&token_series_id);
+        contract.internal_storage_charge(&accounts(1), initial_storage_usage);
+        assert_eq!(contract.accounts.get(&accounts(1)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "not enough storage balance")]
+    fn storage_charge_rejects_growth_beyond_the_balance() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        contract.internal_storage_deposit(&accounts(1), contract.internal_min_storage_balance());
+        let token_series_id: TokenSeriesId = "1".to_string();
+
+        let initial_storage_usage = env::storage_usage();
+        contract.token_series_by_id.insert(&token_series_id, &sunset_series(&token_series_id, accounts(1)));
+        contract.internal_storage_charge(&accounts(1), initial_storage_usage);
+    }
+
+    #[test]
+    fn reset_series_token_metadata_drops_full_copies_page_by_page() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let mut token_series = sunset_series(&token_series_id, accounts(1));
+        let token_ids: Vec<TokenId> = (1..=3).map(|edition| format!("{}{}{}", token_series_id, TOKEN_DELIMETER, edition)).collect();
+        for (edition, token_id) in (1..=3).zip(token_ids.iter()) {
+            token_series.tokens.insert(token_id);
+            let metadata = token_metadata_for_edition(&token_series, edition, Some("1".to_string()));
+            contract.tokens.token_metadata_by_id.as_mut().unwrap().insert(token_id, &metadata);
+        }
+        contract.token_series_by_id.insert(&token_series_id, &token_series);
+
+        contract.nft_reset_series_token_metadata(token_series_id.clone(), None, Some(2));
+        let token_metadata_by_id = contract.tokens.token_metadata_by_id.as_ref().unwrap();
+        assert_eq!(token_metadata_by_id.get(&token_ids[0]), Some(token_metadata_overrides(Some("1".to_string()))));
+        assert_eq!(token_metadata_by_id.get(&token_ids[1]), Some(token_metadata_overrides(Some("1".to_string()))));
+        assert!(token_metadata_by_id.get(&token_ids[2]).unwrap().title.is_some());
+
+        contract.nft_reset_series_token_metadata(token_series_id, Some(U128(2)), None);
+        let token_metadata_by_id = contract.tokens.token_metadata_by_id.as_ref().unwrap();
+        assert_eq!(token_metadata_by_id.get(&token_ids[2]), Some(token_metadata_overrides(Some("1".to_string()))));
+    }
+
+    #[test]
+    fn update_without_propagating_keeps_tokens_on_their_metadata_page_by_page() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        contract.internal_storage_deposit(&accounts(1), contract.internal_min_storage_balance() + ONE_NEAR);
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let mut token_series = sunset_series(&token_series_id, accounts(1));
+        let token_ids: Vec<TokenId> = (1..=3).map(|edition| format!("{}{}{}", token_series_id, TOKEN_DELIMETER, edition)).collect();
+        for token_id in token_ids.iter() {
+            token_series.tokens.insert(token_id);
+            contract.tokens.owner_by_id.insert(token_id, &accounts(2));
+            contract.tokens.token_metadata_by_id.as_mut().unwrap().insert(token_id, &token_metadata_overrides(None));
+        }
+        contract.token_series_by_id.insert(&token_series_id, &token_series);
+        let sunrise = TokenMetadata {
+            title: Some("Sunrise".to_string()),
+            ..token_metadata_overrides(None)
+        };
+
+        let version = contract.nft_update_series_metadata(token_series_id.clone(), sunrise.clone(), Some(false), None, Some(2));
+        assert_eq!(version, U64(0));
+        assert_eq!(contract.nft_token(token_ids[0].clone()).unwrap().metadata.unwrap().title, Some("Sunset #1".to_string()));
+        assert_eq!(contract.nft_token(token_ids[2].clone()).unwrap().metadata.unwrap().title, Some("Sunset #3".to_string()));
+
+        let version = contract.nft_update_series_metadata(token_series_id, sunrise, Some(false), Some(U128(2)), Some(2));
+        assert_eq!(version, U64(1));
+        for token_id in token_ids {
+            assert!(contract.nft_token(token_id).unwrap().metadata.unwrap().title.unwrap().starts_with("Sunset"));
+        }
+    }
+
+    fn series_proceeds(contract: &Contract, account_id: &AccountId, token_series_id: &TokenSeriesId) -> Balance {
+        contract
+            .proceeds
+            .get(account_id)
+            .and_then(|proceeds| proceeds.get(NEAR).and_then(|by_series| by_series.get(token_series_id).copied()))
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn sale_takes_the_platform_fee_rounded_down() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let token_series = sunset_series(&token_series_id, accounts(1));
+
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 1_003, &accounts(2), None);
+        assert!(contract.platform_fees.is_empty());
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 1_003);
+
+        contract.platform_fee_bps = 250;
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 1_003, &accounts(2), None);
+        assert_eq!(contract.platform_fees[NEAR], 25);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 1_003 + 978);
+    }
+
+    #[test]
+    fn sale_rewards_the_referrer_but_not_a_self_referral() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        contract.platform_fee_bps = 250;
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let mut token_series = sunset_series(&token_series_id, accounts(1));
+        token_series.referral_bps = 500;
+
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 1_000, &accounts(2), Some(&accounts(3)));
+        let referrer = contract.referrers.get(&accounts(3)).unwrap();
+        assert_eq!(referrer.mints, 1);
+        assert_eq!(referrer.earned[NEAR], 50);
+        assert_eq!(referrer.claimable[NEAR], 50);
+        assert_eq!(contract.platform_fees[NEAR], 25);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 925);
+
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 1_000, &accounts(3), Some(&accounts(3)));
+        assert_eq!(contract.referrers.get(&accounts(3)).unwrap().mints, 1);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 925 + 975);
+
+        // the reward never eats into the platform fee
+        token_series.referral_bps = 10_000;
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 1_000, &accounts(2), Some(&accounts(3)));
+        assert_eq!(contract.referrers.get(&accounts(3)).unwrap().claimable[NEAR], 50 + 975);
+        assert_eq!(contract.platform_fees[NEAR], 75);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 925 + 975);
+    }
+
+    #[test]
+    fn primary_split_rounding_dust_goes_to_creator() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        contract.platform_fee_bps = 1_000;
+        let token_series_id: TokenSeriesId = "1".to_string();
+        let mut token_series = sunset_series(&token_series_id, accounts(1));
+        token_series.primary_split = vec![(accounts(2), 3_333), (accounts(3), 3_333)].into_iter().collect();
+
+        contract.internal_credit_sale(&token_series_id, &token_series, NEAR, 110, &accounts(4), None);
+        assert_eq!(contract.platform_fees[NEAR], 11);
+        assert_eq!(series_proceeds(&contract, &accounts(2), &token_series_id), 32);
+        assert_eq!(series_proceeds(&contract, &accounts(3), &token_series_id), 32);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &token_series_id), 35);
+    }
+
+    #[test]
+    fn creator_withdraws_near_proceeds_of_every_series() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(1), NEAR, 100);
+        contract.internal_credit_proceeds(&"2".to_string(), &accounts(1), NEAR, 20);
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(2), NEAR, 7);
+
+        match contract.creator_withdraw_proceeds(None) {
+            PromiseOrValue::Value(amount) => assert_eq!(amount, U128(120)),
+            PromiseOrValue::Promise(_) => panic!("NEAR proceeds are paid out directly"),
+        }
+        assert!(contract.proceeds.get(&accounts(1)).is_none());
+        assert_eq!(series_proceeds(&contract, &accounts(2), &"1".to_string()), 7);
+    }
+
+    #[test]
+    fn failed_ft_withdrawal_restores_proceeds() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(1)).attached_deposit(1).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let ft_token_id = accounts(5);
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(1), ft_token_id.as_str(), 100);
+        contract.internal_credit_proceeds(&"2".to_string(), &accounts(1), ft_token_id.as_str(), 20);
+
+        contract.creator_withdraw_proceeds(Some(ft_token_id.clone()));
+        assert!(contract.proceeds.get(&accounts(1)).is_none());
+
+        let proceeds: HashMap<TokenSeriesId, U128> = vec![("1".to_string(), U128(100)), ("2".to_string(), U128(20))].into_iter().collect();
+        testing_env!(
+            VMContextBuilder::new().current_account_id(accounts(0)).predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![near_sdk::PromiseResult::Failed],
+        );
+        assert_eq!(contract.on_withdraw_proceeds(accounts(1), ft_token_id.clone(), proceeds), U128(0));
+        assert_eq!(contract.proceeds.get(&accounts(1)).unwrap()[ft_token_id.as_str()], vec![("1".to_string(), 100), ("2".to_string(), 20)].into_iter().collect());
+    }
+
+    #[test]
+    fn handoff_moves_only_the_series_proceeds() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let ft_token_id = accounts(5);
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(1), NEAR, 100);
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(1), ft_token_id.as_str(), 30);
+        contract.internal_credit_proceeds(&"2".to_string(), &accounts(1), NEAR, 20);
+        contract.internal_credit_proceeds(&"1".to_string(), &accounts(2), NEAR, 5);
+
+        contract.internal_move_series_proceeds(&"1".to_string(), &accounts(1), &accounts(2));
+        assert_eq!(series_proceeds(&contract, &accounts(2), &"1".to_string()), 105);
+        assert_eq!(contract.proceeds.get(&accounts(2)).unwrap()[ft_token_id.as_str()][&"1".to_string()], 30);
+        let proceeds = contract.proceeds.get(&accounts(1)).unwrap();
+        assert_eq!(proceeds.len(), 1);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &"2".to_string()), 20);
+        assert_eq!(series_proceeds(&contract, &accounts(1), &"1".to_string()), 0);
+
+        contract.internal_move_series_proceeds(&"2".to_string(), &accounts(1), &accounts(2));
+        assert!(contract.proceeds.get(&accounts(1)).is_none());
+    }
+}
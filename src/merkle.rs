@@ -0,0 +1,163 @@
+//! Merkle allowlists: leaves are `sha256("{account_id}:{quota}")` and pairs are hashed
+//! in sorted order, so a proof is just the list of sibling hashes from leaf to root.
+
+use crate::*;
+
+pub fn leaf_hash(account_id: &AccountId, quota: u32) -> Vec<u8> {
+    env::sha256(format!("{}{}{}", account_id, TOKEN_DELIMETER, quota).as_bytes())
+}
+
+fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    env::sha256(&[first, second].concat())
+}
+
+pub fn verify_proof(root: &[u8], leaf: Vec<u8>, proof: &[Vec<u8>]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == root
+}
+
+/// Off-chain builder for allowlist roots and proofs.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tree {
+    use super::*;
+
+    const CSV_HEADER: &str = "account_id,quota";
+
+    pub struct AllowlistTree {
+        entries: Vec<(AccountId, u32)>,
+        /// `layers[0]` holds the sorted leaves, the last layer holds the root
+        layers: Vec<Vec<Vec<u8>>>,
+    }
+
+    impl AllowlistTree {
+        pub fn new(entries: Vec<(AccountId, u32)>) -> Self {
+            assert!(!entries.is_empty(), "allowlist is empty");
+            let mut leaves: Vec<Vec<u8>> = entries.iter().map(|(account_id, quota)| leaf_hash(account_id, *quota)).collect();
+            leaves.sort();
+
+            let mut layers = vec![leaves];
+            while layers.last().unwrap().len() > 1 {
+                let next = layers
+                    .last()
+                    .unwrap()
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [a, b] => hash_pair(a, b),
+                        [a] => a.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                layers.push(next);
+            }
+            AllowlistTree { entries, layers }
+        }
+
+        /// Parses `account_id,quota` lines. Blank lines, `#` comments and an `account_id,quota` header are skipped.
+        pub fn from_csv(csv: &str) -> Result<Self, String> {
+            let mut entries = Vec::new();
+            for (line_number, line) in csv.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') || (line_number == 0 && line.replace(' ', "") == CSV_HEADER) {
+                    continue;
+                }
+                let (account_id, quota) = line
+                    .split_once(',')
+                    .ok_or_else(|| format!("line {}: expected `account_id,quota`", line_number + 1))?;
+                let quota: u32 = quota
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("line {}: invalid quota: {}", line_number + 1, e))?;
+                let account_id: AccountId = account_id
+                    .trim()
+                    .parse()
+                    .map_err(|e| format!("line {}: invalid account id: {}", line_number + 1, e))?;
+                entries.push((account_id, quota));
+            }
+            if entries.is_empty() {
+                return Err("allowlist is empty".to_string());
+            }
+            Ok(Self::new(entries))
+        }
+
+        pub fn root(&self) -> Vec<u8> {
+            self.layers.last().unwrap()[0].clone()
+        }
+
+        pub fn entries(&self) -> &[(AccountId, u32)] {
+            &self.entries
+        }
+
+        /// Returns the quota and proof of `account_id`, if it is on the allowlist.
+        pub fn proof(&self, account_id: &AccountId) -> Option<(u32, Vec<Vec<u8>>)> {
+            let (_, quota) = self.entries.iter().find(|(id, _)| id == account_id)?;
+            let leaf = leaf_hash(account_id, *quota);
+            let mut index = self.layers[0].iter().position(|node| *node == leaf)?;
+
+            let mut proof = Vec::new();
+            for layer in &self.layers[..self.layers.len() - 1] {
+                let sibling = index ^ 1;
+                if sibling < layer.len() {
+                    proof.push(layer[sibling].clone());
+                }
+                index /= 2;
+            }
+            Some((*quota, proof))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tree::AllowlistTree;
+    use super::*;
+
+    fn allowlist(size: usize) -> AllowlistTree {
+        AllowlistTree::new(
+            (0..size)
+                .map(|i| (AccountId::new_unchecked(format!("buyer{}.near", i)), i as u32 + 1))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn proofs_verify_against_root() {
+        // 5 leaves leave an unpaired node on the first two layers
+        for size in 1..=5 {
+            let tree = allowlist(size);
+            for (account_id, quota) in tree.entries() {
+                let (proof_quota, proof) = tree.proof(account_id).unwrap();
+                assert_eq!(proof_quota, *quota);
+                assert!(verify_proof(&tree.root(), leaf_hash(account_id, *quota), &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn single_entry_root_is_its_leaf() {
+        let tree = allowlist(1);
+        let (account_id, quota) = &tree.entries()[0];
+        assert_eq!(tree.root(), leaf_hash(account_id, *quota));
+        assert!(tree.proof(account_id).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn proof_rejects_other_quota_or_account() {
+        let tree = allowlist(5);
+        let (account_id, quota) = tree.entries()[4].clone();
+        let (_, proof) = tree.proof(&account_id).unwrap();
+        assert!(!verify_proof(&tree.root(), leaf_hash(&account_id, quota + 1), &proof));
+
+        let outsider = AccountId::new_unchecked("outsider.near".to_string());
+        assert!(tree.proof(&outsider).is_none());
+        assert!(!verify_proof(&tree.root(), leaf_hash(&outsider, quota), &proof));
+    }
+
+    #[test]
+    fn csv_skips_the_header_but_not_a_malformed_first_row() {
+        let tree = AllowlistTree::from_csv("account_id, quota\nbuyer0.near,1\n# comment\n\nbuyer1.near,2\n").unwrap();
+        assert_eq!(tree.entries().len(), 2);
+
+        assert!(AllowlistTree::from_csv("buyer0.near,one\nbuyer1.near,2\n").is_err());
+    }
+}
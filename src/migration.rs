@@ -0,0 +1,193 @@
+//! State layout of the first deployed contract version, read by `migrate` and `migrate_token_series`.
+
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct OldTokenSeries {
+    metadata: TokenMetadata,
+    creator_id: AccountId,
+    tokens: UnorderedSet<TokenId>,
+    price: Option<Balance>,
+    ft_token_id: Option<AccountId>,
+    is_mintable: bool,
+    royalty: HashMap<AccountId, u32>
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct OldContract {
+    pub tokens: NonFungibleToken,
+    pub metadata: LazyOption<NFTContractMetadata>,
+    pub token_series_by_id: UnorderedMap<TokenSeriesId, OldTokenSeries>,
+    pub accounts: LookupMap<AccountId, Balance>
+}
+
+impl TokenSeries {
+    /// Rebuilds an old series with default options, keeping its tokens.
+    /// Old prices were only set together with `ft_token_id`, so they carry over as the price in that token.
+    pub(crate) fn from_old(token_series_id: &TokenSeriesId, old: OldTokenSeries) -> Self {
+        let mut token_series = TokenSeries::new(token_series_id, old.metadata, old.creator_id, old.royalty);
+        // editions were numbered by the size of the series, burned tokens were never removed from it
+        token_series.minted = old.tokens.len();
+        token_series.tokens = old.tokens;
+        token_series.is_mintable = old.is_mintable;
+        if let (Some(price), Some(ft_token_id)) = (old.price, old.ft_token_id) {
+            token_series.prices.insert(ft_token_id.to_string(), PricingMode::Fixed { price: price.into() });
+        }
+        token_series
+    }
+}
+
+/// Series still in the first deployed layout, moved over in batches by `migrate_token_series`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct SeriesMigration {
+    old_token_series: UnorderedMap<TokenSeriesId, OldTokenSeries>,
+    /// Moved series whose token set is being cleared of burned tokens, and the next index to check
+    pruning: Option<(TokenSeriesId, u64)>
+}
+
+impl SeriesMigration {
+    pub(crate) fn new(old_token_series: UnorderedMap<TokenSeriesId, OldTokenSeries>) -> Self {
+        SeriesMigration { old_token_series, pruning: None }
+    }
+}
+
+impl Contract {
+    /// Moves old series into `token_series_by_id` and drops burned tokens from their token sets,
+    /// the old `nft_burn` left them there. Each series moved and each token checked counts against `limit`.
+    /// Returns whether the migration is done.
+    pub(crate) fn internal_migrate_token_series(&mut self, limit: u64) -> bool {
+        let mut migration = match self.series_migration.take() {
+            Some(migration) => migration,
+            None => return true,
+        };
+        let mut budget = limit;
+        while budget > 0 {
+            if let Some((token_series_id, mut index)) = migration.pruning.take() {
+                let mut token_series = self.token_series_by_id.get(&token_series_id).unwrap();
+                while budget > 0 && index < token_series.tokens.len() {
+                    let token_id = token_series.tokens.as_vector().get(index).unwrap();
+                    if self.tokens.owner_by_id.contains_key(&token_id) {
+                        index += 1;
+                    } else {
+                        // the last token takes its place, so the index is checked again
+                        token_series.tokens.remove(&token_id);
+                        token_series.burned += 1;
+                    }
+                    budget -= 1;
+                }
+                if index < token_series.tokens.len() {
+                    migration.pruning = Some((token_series_id.clone(), index));
+                }
+                self.token_series_by_id.insert(&token_series_id, &token_series);
+            } else {
+                let len = migration.old_token_series.len();
+                if len == 0 {
+                    break;
+                }
+                let token_series_id = migration.old_token_series.keys_as_vector().get(len - 1).unwrap();
+                let old = migration.old_token_series.remove(&token_series_id).unwrap();
+                self.token_series_by_id.insert(&token_series_id, &TokenSeries::from_old(&token_series_id, old));
+                migration.pruning = Some((token_series_id, 0));
+                budget -= 1;
+            }
+        }
+
+        let done = migration.pruning.is_none() && migration.old_token_series.is_empty();
+        if !done {
+            self.series_migration = Some(migration);
+        }
+        done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn old_series(token_series_id: &str, token_count: u64) -> OldTokenSeries {
+        let mut tokens = UnorderedSet::new(
+            StorageKey::TokensBySeriesInner {
+                token_series: token_series_id.to_string(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        );
+        for edition in 1..=token_count {
+            tokens.insert(&format!("{}{}{}", token_series_id, TOKEN_DELIMETER, edition));
+        }
+        OldTokenSeries {
+            metadata: TokenMetadata {
+                title: Some("Sunset".to_string()),
+                ..token_metadata_overrides(None)
+            },
+            creator_id: accounts(1),
+            tokens,
+            price: Some(100),
+            ft_token_id: Some(accounts(2)),
+            is_mintable: true,
+            royalty: HashMap::new(),
+        }
+    }
+
+    fn migrating_contract(token_series_ids: Vec<&str>, token_count: u64) -> Contract {
+        let mut contract = Contract::new_default_meta(accounts(0));
+        let mut old_token_series = UnorderedMap::new(StorageKey::TokenSeriesById);
+        for token_series_id in token_series_ids {
+            old_token_series.insert(&token_series_id.to_string(), &old_series(token_series_id, token_count));
+            for edition in 1..=token_count {
+                contract.tokens.owner_by_id.insert(&format!("{}{}{}", token_series_id, TOKEN_DELIMETER, edition), &accounts(3));
+            }
+        }
+        contract.token_series_by_id = UnorderedMap::new(StorageKey::MigratedTokenSeriesById);
+        contract.series_migration = Some(SeriesMigration::new(old_token_series));
+        contract
+    }
+
+    #[test]
+    fn series_are_migrated_in_batches() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = migrating_contract(vec!["1", "2", "3"], 2);
+
+        // a series and its two tokens, then the next series
+        assert!(!contract.internal_migrate_token_series(4));
+        assert_eq!(contract.token_series_by_id.len(), 2);
+        assert!(contract.internal_migrate_token_series(10));
+        assert!(contract.series_migration.is_none());
+        assert!(contract.internal_migrate_token_series(10));
+
+        let token_series = contract.token_series_by_id.get(&"1".to_string()).unwrap();
+        assert_eq!(token_series.creator_id, accounts(1));
+        assert_eq!(token_series.tokens.len(), 2);
+        assert_eq!(token_series.minted, 2);
+        assert_eq!(token_series.current_price(accounts(2).as_str()), Some(100));
+    }
+
+    #[test]
+    fn burned_tokens_are_dropped_from_migrated_series() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = migrating_contract(vec!["1"], 5);
+        for edition in &[2, 3, 5] {
+            contract.tokens.owner_by_id.remove(&format!("1{}{}", TOKEN_DELIMETER, edition));
+        }
+
+        assert!(!contract.internal_migrate_token_series(3));
+        assert!(contract.internal_migrate_token_series(10));
+
+        let token_series = contract.token_series_by_id.get(&"1".to_string()).unwrap();
+        assert_eq!(token_series.minted, 5);
+        assert_eq!(token_series.burned, 3);
+        let mut token_ids = token_series.tokens.to_vec();
+        token_ids.sort();
+        assert_eq!(token_ids, vec![format!("1{}1", TOKEN_DELIMETER), format!("1{}4", TOKEN_DELIMETER)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "series migration in progress")]
+    fn series_cannot_be_created_during_the_migration() {
+        testing_env!(VMContextBuilder::new().predecessor_account_id(accounts(0)).build());
+        let mut contract = migrating_contract(vec!["1"], 1);
+        contract.internal_create_series(accounts(1), token_metadata_overrides(None), None);
+    }
+}
@@ -1,4 +1,3 @@
-use near_contract_standards::non_fungible_token::events::NftTransfer;
 
 use crate::*;
 
@@ -9,11 +8,11 @@ impl Contract {
     pub fn nft_payout(
         &self, 
         token_id: TokenId,
-        balance: U128
+        balance: U128,
+        max_len_payout: Option<u32>
     ) -> Payout {
-        let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
-        let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
-        self.internal_nft_payout(token_series_id, balance.into())
+        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("no token");
+        self.internal_nft_payout(&token_id, &owner_id, balance.into(), max_len_payout)
     }
     
     #[payable]
@@ -22,61 +21,19 @@ impl Contract {
         receiver_id: AccountId,
         token_id: TokenId,
         approval_id: Option<u64>,
-        balance: Option<U128>,
+        memo: Option<String>,
+        balance: U128,
         max_len_payout: Option<u32>
-    ) -> Option<Payout> {
+    ) -> Payout {
         assert_one_yocto();
     
-        let sender_id = env::predecessor_account_id();
-        // Transfer
-        let previous_token = self.nft_token(token_id.clone()).expect("no token");
-        self.tokens.nft_transfer(receiver_id.clone(), token_id.clone(), approval_id, None);
-    
-        // Payout calculation
-        let previous_owner_id = previous_token.owner_id;
-        let mut total_perpetual = 0;
-        let payout = if let Some(balance) = balance {
-            let balance_u128: u128 = u128::from(balance);
-            let mut payout: Payout = Payout { payout: HashMap::new() };
-    
-            let mut token_id_iter = token_id.split(TOKEN_DELIMETER);
-            let token_series_id = token_id_iter.next().unwrap().parse().unwrap();
-            let royalty = self.token_series_by_id.get(&token_series_id).expect("no type").royalty;
-    
-            assert!(royalty.len() as u32 <= max_len_payout.unwrap(), "Market cannot payout to that many receivers");
-            for (k, v) in royalty.iter() {
-                let key = k.clone();
-                if key != previous_owner_id {
-                    payout.payout.insert(key, royalty_to_payout(*v, balance_u128));
-                    total_perpetual += *v;
-                }
-            }
-    
-            assert!(
-                total_perpetual <= 10000,
-                "Total payout overflow"
-            );
-    
-            payout.payout.insert(previous_owner_id.clone(), royalty_to_payout(10000 - total_perpetual, balance_u128));
-            Some(payout)
-        } else {
-            None
-        };
-
-        let authorized_id : Option<&AccountId> = if sender_id != previous_owner_id {
-            Some(&sender_id)
-        } else {
-            None
-        };
+        let previous_owner_id = self.tokens.owner_by_id.get(&token_id).expect("no token");
+        self.internal_assert_transferable(&token_id);
+        // Payout calculation, against the owner before the transfer
+        let payout = self.internal_nft_payout(&token_id, &previous_owner_id, balance.into(), max_len_payout);
 
-        NftTransfer {
-            old_owner_id: &previous_owner_id,
-            new_owner_id: &receiver_id,
-            token_ids: &[&token_id],
-            authorized_id: authorized_id,
-            memo: None,
-        }
-        .emit();
+        // Transfer, emits the transfer event
+        self.tokens.nft_transfer(receiver_id, token_id, approval_id, memo);
     
         payout
     }
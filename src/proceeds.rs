@@ -0,0 +1,166 @@
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::{is_promise_success, log};
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers all unclaimed mint proceeds of the caller out of the contract.
+    /// Proceeds are paid in NEAR when `ft_token_id` is omitted, otherwise in that fungible token.
+    #[payable]
+    pub fn creator_withdraw_proceeds(&mut self, ft_token_id: Option<AccountId>) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let creator_id = env::predecessor_account_id();
+
+        match ft_token_id {
+            None => {
+                let amount: Balance = self.internal_take_proceeds(&creator_id, NEAR).values().sum();
+                assert!(amount > 0, " no proceeds to withdraw");
+                Promise::new(creator_id).transfer(amount);
+                PromiseOrValue::Value(amount.into())
+            }
+            Some(ft_token_id) => {
+                let proceeds = self.internal_take_proceeds(&creator_id, ft_token_id.as_str());
+                let amount: Balance = proceeds.values().sum();
+                assert!(amount > 0, " no proceeds to withdraw");
+                ext_fungible_token::ft_transfer(
+                    creator_id.clone(),
+                    amount.into(),
+                    None,
+                    ft_token_id.clone(),
+                    1,
+                    GAS_FOR_FT_TRANSFER,
+                )
+                .then(ext_self::on_withdraw_proceeds(
+                    creator_id,
+                    ft_token_id,
+                    proceeds.into_iter().map(|(k, v)| (k, U128(v))).collect(),
+                    env::current_account_id(),
+                    0,
+                    GAS_FOR_RESOLVE_WITHDRAW,
+                ))
+                .into()
+            }
+        }
+    }
+
+    /// Restores the withdrawn proceeds if the `ft_transfer` to the creator failed.
+    #[private]
+    pub fn on_withdraw_proceeds(
+        &mut self,
+        creator_id: AccountId,
+        ft_token_id: AccountId,
+        proceeds: HashMap<TokenSeriesId, U128>
+    ) -> U128 {
+        if is_promise_success() {
+            return proceeds.values().map(|v| v.0).sum::<Balance>().into();
+        }
+
+        log!("Failed to transfer {} proceeds to {}, restoring balance", ft_token_id, creator_id);
+        for (token_series_id, amount) in proceeds.iter() {
+            self.internal_credit_proceeds(token_series_id, &creator_id, ft_token_id.as_str(), amount.0);
+        }
+        U128(0)
+    }
+
+    /// Sets the platform fee taken from every primary sale, in basis points.
+    #[payable]
+    pub fn set_platform_fee(&mut self, fee_bps: u32) {
+        assert_one_yocto();
+        assert_eq!(env::predecessor_account_id(), self.tokens.owner_id, "Only owner");
+        assert!(fee_bps <= MAX_PLATFORM_FEE_BPS, "Exceeds maximum platform fee -> {}", MAX_PLATFORM_FEE_BPS);
+        self.platform_fee_bps = fee_bps;
+    }
+
+    /// Transfers the unclaimed platform fees in NEAR, or in `ft_token_id` when given, to the owner.
+    #[payable]
+    pub fn owner_withdraw_platform_fees(&mut self, ft_token_id: Option<AccountId>) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        assert_eq!(owner_id, self.tokens.owner_id, "Only owner");
+
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+        let amount = self.platform_fees.remove(currency).unwrap_or(0);
+        assert!(amount > 0, " no platform fees to withdraw");
+        match ft_token_id {
+            None => {
+                Promise::new(owner_id).transfer(amount);
+                PromiseOrValue::Value(amount.into())
+            }
+            Some(ft_token_id) => ext_fungible_token::ft_transfer(
+                owner_id,
+                amount.into(),
+                None,
+                ft_token_id.clone(),
+                1,
+                GAS_FOR_FT_TRANSFER,
+            )
+            .then(ext_self::on_withdraw_platform_fees(
+                ft_token_id,
+                amount.into(),
+                env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_WITHDRAW,
+            ))
+            .into(),
+        }
+    }
+
+    /// Transfers the unclaimed referral rewards of the caller in NEAR, or in `ft_token_id` when given.
+    #[payable]
+    pub fn referrer_withdraw_rewards(&mut self, ft_token_id: Option<AccountId>) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        let referrer_id = env::predecessor_account_id();
+
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+        let amount = self.internal_take_referral_rewards(&referrer_id, currency);
+        assert!(amount > 0, " no referral rewards to withdraw");
+        match ft_token_id {
+            None => {
+                Promise::new(referrer_id).transfer(amount);
+                PromiseOrValue::Value(amount.into())
+            }
+            Some(ft_token_id) => ext_fungible_token::ft_transfer(
+                referrer_id.clone(),
+                amount.into(),
+                None,
+                ft_token_id.clone(),
+                1,
+                GAS_FOR_FT_TRANSFER,
+            )
+            .then(ext_self::on_withdraw_referral_rewards(
+                referrer_id,
+                ft_token_id,
+                amount.into(),
+                env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_WITHDRAW,
+            ))
+            .into(),
+        }
+    }
+
+    /// Restores the withdrawn referral rewards if the `ft_transfer` to the referrer failed.
+    #[private]
+    pub fn on_withdraw_referral_rewards(&mut self, referrer_id: AccountId, ft_token_id: AccountId, amount: U128) -> U128 {
+        if is_promise_success() {
+            return amount;
+        }
+
+        log!("Failed to transfer {} referral rewards to {}, restoring balance", ft_token_id, referrer_id);
+        self.internal_credit_referral_rewards(&referrer_id, ft_token_id.as_str(), amount.0);
+        U128(0)
+    }
+
+    /// Restores the withdrawn platform fees if the `ft_transfer` to the owner failed.
+    #[private]
+    pub fn on_withdraw_platform_fees(&mut self, ft_token_id: AccountId, amount: U128) -> U128 {
+        if is_promise_success() {
+            return amount;
+        }
+
+        log!("Failed to transfer {} platform fees, restoring balance", ft_token_id);
+        *self.platform_fees.entry(ft_token_id.to_string()).or_insert(0) += amount.0;
+        U128(0)
+    }
+}
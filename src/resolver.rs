@@ -1,6 +1,6 @@
 
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
-use near_sdk::serde_json;
+use near_sdk::{log, serde_json};
 
 use crate::*;
 
@@ -8,14 +8,16 @@ use crate::*;
 #[serde(crate = "near_sdk::serde")]
 pub struct TokenReceiverMessage {
     token_series_id: TokenSeriesId,
-    receiver_id: AccountId
+    receiver_id: AccountId,
+    allowlist_proof: Option<AllowlistProof>,
+    referrer_id: Option<AccountId>
 }
 
 #[near_bindgen]
-#[allow(unreachable_code)]
 impl FungibleTokenReceiver for Contract {
     /// Callback on receiving tokens by this contract.
-    /// `msg` format is either "" for deposit or `TokenReceiverMessage`.
+    /// `msg` must be a `TokenReceiverMessage`. Whatever is not spent on the mint, including the
+    /// full amount when the mint cannot happen, is returned to be refunded by `ft_resolve_transfer`.
     fn ft_on_transfer(
         &mut self,
         sender_id: AccountId,
@@ -24,12 +26,28 @@ impl FungibleTokenReceiver for Contract {
     ) -> PromiseOrValue<U128> {
         let token_in = env::predecessor_account_id();
         if msg.is_empty() {
-            panic!("no msg found")
-        } else {
-            let message = serde_json::from_str::<TokenReceiverMessage>(&msg).expect("ERR_MSG_WRONG_FORMAT");
-            let info = self.internal_mint_with_token(sender_id, token_in, amount.into(), message.token_series_id, message.receiver_id.clone());
-            NftMint { owner_id: &message.receiver_id, token_ids: &[&info.0], memo: None }.emit();
-            PromiseOrValue::Value(U128(info.1))
+            log!("no msg found, refunding {}", amount.0);
+            return PromiseOrValue::Value(amount);
+        }
+        let message = match serde_json::from_str::<TokenReceiverMessage>(&msg) {
+            Ok(message) => message,
+            Err(err) => {
+                log!("ERR_MSG_WRONG_FORMAT: {}, refunding {}", err, amount.0);
+                return PromiseOrValue::Value(amount);
+            }
+        };
+
+        let initial_storage_usage = env::storage_usage();
+        match self.internal_mint_with_token(sender_id.clone(), token_in, amount.into(), message.token_series_id, message.receiver_id.clone(), message.allowlist_proof.as_ref(), message.referrer_id.as_ref()) {
+            Ok((token_id, unused)) => {
+                self.internal_storage_charge(&sender_id, initial_storage_usage);
+                NftMint { owner_id: &message.receiver_id, token_ids: &[&token_id], memo: None }.emit();
+                PromiseOrValue::Value(U128(unused))
+            }
+            Err(err) => {
+                log!("Mint failed: {}, refunding {}", err.trim(), amount.0);
+                PromiseOrValue::Value(amount)
+            }
         }
     }
-}
\ No newline at end of file
+}
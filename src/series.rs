@@ -1,10 +1,16 @@
 use near_sdk::log;
 
 use crate::*;
+use crate::events::{SeriesCreatorProposed, SeriesCreatorTransfer, SeriesRoyaltyUpdate};
+use crate::utils::{
+    assert_at_least_one_yocto, assert_valid_presale, assert_valid_primary_split, assert_valid_royalty,
+    edition_of, is_full_metadata_copy, merge_token_metadata, token_metadata_for_edition, token_metadata_overrides
+};
 
 #[near_bindgen]
 impl Contract {
     #[payable]
+    #[allow(clippy::too_many_arguments)]
     pub fn nft_create_series(
         &mut self,
         creator_id: Option<AccountId>,
@@ -12,8 +18,23 @@ impl Contract {
         mint_price: Option<U128>, 
         ft_token_id: Option<AccountId>,
         royalty: Option<HashMap<AccountId, u32>>,
-        notify_contract_id: Option<AccountId>
+        notify_contract_id: Option<AccountId>,
+        title_format: Option<TitleFormat>,
+        burnable_by: Option<BurnPolicy>,
+        transferable: Option<bool>,
+        transfer_locked_until: Option<U64>,
+        lock_duration_after_mint: Option<U64>,
+        mint_start: Option<U64>,
+        mint_end: Option<U64>,
+        presale: Option<Presale>,
+        max_per_account: Option<u32>,
+        pricing: Option<PricingMode>,
+        prices: Option<HashMap<String, PricingMode>>,
+        referral_bps: Option<u32>,
+        primary_split: Option<HashMap<AccountId, u32>>
     ) -> TokenSeriesId {
+        let payer_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&payer_id, env::attached_deposit());
         let initial_storage_usage = env::storage_usage();
         let mut caller_id = env::predecessor_account_id();
 
@@ -22,16 +43,52 @@ impl Contract {
             caller_id = creator_id.unwrap();
         }
 
-        let token_series_id = self.internal_create_series(caller_id.clone(), token_metadata.clone(), mint_price, ft_token_id.clone(), royalty);
-        self.internal_set_price(token_series_id.clone(), mint_price, ft_token_id);
+        assert!(mint_price.is_none() || pricing.is_none(), " use either mint_price or pricing");
+        let mut prices = prices.unwrap_or_default();
+        match pricing.or_else(|| mint_price.map(|price| PricingMode::Fixed { price })) {
+            Some(pricing) => {
+                let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+                assert!(!prices.contains_key(currency), " price in {} given twice", currency);
+                prices.insert(currency.to_string(), pricing);
+            }
+            None => assert!(ft_token_id.is_none(), " ft_token_id given without a price"),
+        }
+        let is_free = prices.is_empty();
+        let token_series_id = self.internal_create_series(caller_id.clone(), token_metadata.clone(), royalty);
+        self.internal_set_prices(&token_series_id, prices);
+        let mut token_series = self.token_series_by_id.get(&token_series_id).unwrap();
+        if let Some(title_format) = title_format {
+            token_series.title_format = title_format;
+        }
+        if let Some(burnable_by) = burnable_by {
+            token_series.burnable_by = burnable_by;
+        }
+        if let Some(transferable) = transferable {
+            token_series.transferable = transferable;
+        }
+        token_series.transfer_locked_until = transfer_locked_until.map(u64::from);
+        token_series.lock_duration_after_mint = lock_duration_after_mint.map(u64::from);
+        if let Some(presale) = &presale {
+            assert_valid_presale(presale);
+        }
+        token_series.presale = presale;
+        token_series.max_per_account = max_per_account;
+        if let Some(referral_bps) = referral_bps {
+            assert!(referral_bps <= 10_000, " referral_bps exceeds 10000");
+            token_series.referral_bps = referral_bps;
+        }
+        if let Some(primary_split) = primary_split {
+            assert_valid_primary_split(&primary_split);
+            token_series.primary_split = primary_split;
+        }
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_set_mint_window(&token_series_id, mint_start, mint_end);
 
-        if mint_price.is_none() && token_metadata.copies == Some(1)  {
-            self.accounts.insert(&caller_id, &(env::attached_deposit() - (env::storage_usage() - initial_storage_usage) as u128 * env::storage_byte_cost() as u128));
-            let token_id = self.internal_nft_mint_series(caller_id.clone(), token_series_id.clone(), caller_id.clone());
+        if is_free && token_metadata.copies == Some(1)  {
+            let token_id = self.internal_nft_mint_series(token_series_id.clone(), caller_id.clone(), &caller_id);
             NftMint { owner_id: &caller_id, token_ids: &[&token_id], memo: None }.emit();
-        } else {
-            refund_extra_storage_deposit(env::storage_usage() - initial_storage_usage, 0);
         }
+        self.internal_storage_charge(&payer_id, initial_storage_usage);
         if notify_contract_id.is_some() {
             let args_json = json!({
                 "token_series_id": token_series_id
@@ -44,22 +101,74 @@ impl Contract {
         token_series_id
     }
 
+    /// Sets the price in NEAR, or in `ft_token_id` when given, either a fixed `mint_price` or a `pricing` mode
+    /// such as a Dutch auction. `nft_remove_series_price` stops accepting a currency.
+    #[payable]
+    pub fn nft_set_series_price(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        mint_price: Option<U128>,
+        ft_token_id: Option<AccountId>,
+        pricing: Option<PricingMode>
+    ) -> Option<U128> {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+
+        assert!(token_series.is_mintable, " token series is not mintable");
+        assert!(mint_price.is_none() || pricing.is_none(), " use either mint_price or pricing");
+        let pricing = pricing
+            .or_else(|| mint_price.map(|price| PricingMode::Fixed { price }))
+            .expect(" mint_price or pricing required");
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str()).to_string();
+        let price = self.internal_set_price(&token_series_id, currency, Some(pricing));
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+        price
+    }
+
+    /// Stops accepting NEAR, or `ft_token_id` when given, for mints of the series.
     #[payable]
-    pub fn nft_set_series_price(&mut self, token_series_id: TokenSeriesId, mint_price: Option<U128>, ft_token_id: Option<AccountId>) -> Option<U128> {
+    pub fn nft_remove_series_price(&mut self, token_series_id: TokenSeriesId, ft_token_id: Option<AccountId>) {
         assert_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
         let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
         assert_eq!(
-            env::predecessor_account_id(),
+            creator_id,
             token_series.creator_id,
             " Creator only"
         );
 
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str()).to_string();
+        self.internal_set_price(&token_series_id, currency, None);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Replaces the price list, keyed by `near` or a fungible token contract. An empty list takes the series off sale.
+    #[payable]
+    pub fn nft_set_series_prices(&mut self, token_series_id: TokenSeriesId, prices: HashMap<String, PricingMode>) -> HashMap<String, U128> {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
         assert_eq!(
-            token_series.is_mintable,
-            true,
-            " token series is not mintable"
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
         );
-        self.internal_set_price(token_series_id, mint_price, ft_token_id)
+
+        assert!(token_series.is_mintable, " token series is not mintable");
+        self.internal_set_prices(&token_series_id, prices);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+        self.token_series_by_id.get(&token_series_id).unwrap().current_prices()
     }
 
     #[payable]
@@ -104,7 +213,7 @@ impl Contract {
             " Creator only"
         );
 
-        let minted_copies = token_series.tokens.len();
+        let minted_copies = token_series.issued_supply();
         let copies = token_series.metadata.copies.unwrap();
 
         assert!(
@@ -124,6 +233,370 @@ impl Contract {
         self.token_series_by_id.insert(&token_series_id, &token_series);
         U64::from(token_series.metadata.copies.unwrap())
     }
-}
 
+    /// Replaces the series metadata, keeping the previous one in the version history.
+    /// Minted tokens show the new metadata unless `propagate` is `false`, which first stores the current
+    /// metadata in each of them at the creator's storage cost, `limit` tokens of the series from `from_index`
+    /// per call. The metadata is replaced by the call that reaches the last token.
+    #[payable]
+    pub fn nft_update_series_metadata(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        token_metadata: TokenMetadata,
+        propagate: Option<bool>,
+        from_index: Option<U128>,
+        limit: Option<u64>
+    ) -> U64 {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        let creator_id = env::predecessor_account_id();
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        assert!(!token_series.metadata_frozen, " metadata is frozen");
+        assert!(token_metadata.title.is_some(), " token_metadata.title is required");
+
+        let mut is_last_page = true;
+        if !propagate.unwrap_or(true) {
+            let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+            let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+            if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+                for token_id in token_series.tokens.iter().skip(start_index as usize).take(limit) {
+                    let stored = token_metadata_by_id.get(&token_id);
+                    if !is_full_metadata_copy(&stored) {
+                        let metadata = token_metadata_for_edition(&token_series, edition_of(&token_id), None);
+                        let overrides = stored.unwrap_or_else(|| token_metadata_overrides(None));
+                        token_metadata_by_id.insert(&token_id, &merge_token_metadata(metadata, overrides));
+                    }
+                }
+            }
+            is_last_page = (start_index as usize).saturating_add(limit) >= token_series.tokens.len() as usize;
+        }
+
+        if is_last_page {
+            let mut metadata = token_metadata;
+            metadata.copies = token_series.metadata.copies;
+            token_series.metadata_versions.push(&token_series.metadata);
+            token_series.metadata = metadata;
+            self.token_series_by_id.insert(&token_series_id, &token_series);
+        }
+
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+        U64::from(token_series.metadata_versions.len())
+    }
+
+    /// Makes minted tokens holding a full metadata copy follow the series metadata again, `limit` tokens
+    /// of the series from `from_index` at a time. Tokens minted before per-token overrides hold one,
+    /// as do tokens kept on their metadata by an update that did not `propagate`.
+    #[payable]
+    pub fn nft_reset_series_token_metadata(
+        &mut self,
+        token_series_id: TokenSeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>
+    ) {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        let creator_id = env::predecessor_account_id();
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        assert!(!token_series.metadata_frozen, " metadata is frozen");
+
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
+            for token_id in token_series.tokens.iter().skip(start_index as usize).take(limit) {
+                let stored = token_metadata_by_id.get(&token_id);
+                if is_full_metadata_copy(&stored) {
+                    let issued_at = stored.and_then(|metadata| metadata.issued_at);
+                    token_metadata_by_id.insert(&token_id, &token_metadata_overrides(issued_at));
+                }
+            }
+        }
+
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Replaces the royalty recipients of a series. The total royalty can never exceed the one it was created with.
+    #[payable]
+    pub fn nft_set_series_royalty(&mut self, token_series_id: TokenSeriesId, royalty: HashMap<AccountId, u32>) {
+        assert_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        let creator_id = env::predecessor_account_id();
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        assert_valid_royalty(&royalty);
+        let new_total: u32 = royalty.values().sum();
+        assert!(
+            new_total <= token_series.max_royalty,
+            " royalty exceeds the original total : {}",
+            token_series.max_royalty
+        );
+
+        token_series.royalty = royalty;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        SeriesRoyaltyUpdate { token_series_id: &token_series_id, royalty: &token_series.royalty }.emit();
+
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    #[payable]
+    pub fn nft_freeze_series_metadata(&mut self, token_series_id: TokenSeriesId) {
+        assert_one_yocto();
+
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            " Creator only"
+        );
+        assert!(!token_series.metadata_frozen, " metadata already frozen");
+
+        token_series.metadata_frozen = true;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+    }
+
+    #[payable]
+    pub fn nft_set_series_remintable(&mut self, token_series_id: TokenSeriesId, remint_burned: bool) {
+        assert_one_yocto();
+
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            " Creator only"
+        );
 
+        token_series.remint_burned = remint_burned;
+        if let Some(copies) = token_series.metadata.copies {
+            token_series.is_mintable = token_series.issued_supply() < copies;
+        }
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+    }
+
+    #[payable]
+    pub fn nft_set_series_mint_window(&mut self, token_series_id: TokenSeriesId, mint_start: Option<U64>, mint_end: Option<U64>) {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        self.internal_set_mint_window(&token_series_id, mint_start, mint_end);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Sets or clears the allowlist presale.
+    #[payable]
+    pub fn nft_set_series_presale(&mut self, token_series_id: TokenSeriesId, presale: Option<Presale>) {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        if let Some(presale) = &presale {
+            assert_valid_presale(presale);
+        }
+        token_series.presale = presale;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Sets the presale quota of each account.
+    #[payable]
+    pub fn nft_add_to_allowlist(&mut self, token_series_id: TokenSeriesId, accounts: HashMap<AccountId, u32>) {
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+
+        for (account_id, quota) in accounts {
+            self.allowlist.insert(&(token_series_id.clone(), account_id), &quota);
+        }
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    #[payable]
+    pub fn nft_remove_from_allowlist(&mut self, token_series_id: TokenSeriesId, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+
+        for account_id in account_ids {
+            self.allowlist.remove(&(token_series_id.clone(), account_id));
+        }
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Sets the sha256 Merkle root of the presale allowlist, see [`crate::merkle`].
+    #[payable]
+    pub fn nft_set_series_allowlist_root(&mut self, token_series_id: TokenSeriesId, root: Option<Base64VecU8>) {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        if let Some(root) = &root {
+            assert_eq!(root.0.len(), 32, " root must be a sha256 hash");
+        }
+        token_series.allowlist_root = root.map(|root| root.0);
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    #[payable]
+    pub fn nft_set_series_max_per_account(&mut self, token_series_id: TokenSeriesId, max_per_account: Option<u32>) {
+        assert_one_yocto();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            " Creator only"
+        );
+        token_series.max_per_account = max_per_account;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+    }
+
+    /// Sets the share of the price paid to referrers of paid mints, in basis points.
+    #[payable]
+    pub fn nft_set_series_referral_fee(&mut self, token_series_id: TokenSeriesId, referral_bps: u32) {
+        assert_one_yocto();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            env::predecessor_account_id(),
+            token_series.creator_id,
+            " Creator only"
+        );
+        assert!(referral_bps <= 10_000, " referral_bps exceeds 10000");
+        token_series.referral_bps = referral_bps;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+    }
+
+    /// Splits future primary sale proceeds between accounts, in basis points summing to 10000.
+    /// `None` sends them all to the creator again.
+    #[payable]
+    pub fn nft_set_series_primary_split(&mut self, token_series_id: TokenSeriesId, primary_split: Option<HashMap<AccountId, u32>>) {
+        assert_at_least_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        let primary_split = primary_split.unwrap_or_default();
+        if !primary_split.is_empty() {
+            assert_valid_primary_split(&primary_split);
+        }
+        token_series.primary_split = primary_split;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Invites `new_creator_id` to take over the series, `None` withdraws a pending invitation.
+    /// Nothing changes until the invited account calls `nft_accept_series_creator`.
+    #[payable]
+    pub fn nft_propose_series_creator(&mut self, token_series_id: TokenSeriesId, new_creator_id: Option<AccountId>) {
+        assert_one_yocto();
+        let creator_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            creator_id,
+            token_series.creator_id,
+            " Creator only"
+        );
+        if let Some(new_creator_id) = &new_creator_id {
+            assert_ne!(*new_creator_id, creator_id, " already the creator");
+        }
+
+        token_series.pending_creator_id = new_creator_id;
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        SeriesCreatorProposed {
+            token_series_id: &token_series_id,
+            creator_id: &creator_id,
+            proposed_creator_id: token_series.pending_creator_id.as_ref(),
+        }
+        .emit();
+        self.internal_storage_charge(&creator_id, initial_storage_usage);
+    }
+
+    /// Takes over a series the caller was invited to, together with its unclaimed proceeds.
+    /// The previous creator's share of the primary split passes to the caller as well.
+    /// Storage is paid from the caller's storage balance.
+    #[payable]
+    pub fn nft_accept_series_creator(&mut self, token_series_id: TokenSeriesId) {
+        let new_creator_id = env::predecessor_account_id();
+        self.internal_storage_deposit(&new_creator_id, env::attached_deposit());
+        let initial_storage_usage = env::storage_usage();
+        let mut token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        assert_eq!(
+            token_series.pending_creator_id.as_ref(),
+            Some(&new_creator_id),
+            " not the proposed creator"
+        );
+
+        let old_creator_id = token_series.creator_id.clone();
+        if let Some(bps) = token_series.primary_split.remove(&old_creator_id) {
+            *token_series.primary_split.entry(new_creator_id.clone()).or_insert(0) += bps;
+        }
+        token_series.creator_id = new_creator_id.clone();
+        token_series.pending_creator_id = None;
+        token_series.creator_history.push(&CreatorTransfer {
+            old_creator_id: old_creator_id.clone(),
+            new_creator_id: new_creator_id.clone(),
+            transferred_at: env::block_timestamp().into(),
+        });
+        self.token_series_by_id.insert(&token_series_id, &token_series);
+        self.internal_move_series_proceeds(&token_series_id, &old_creator_id, &new_creator_id);
+
+        SeriesCreatorTransfer {
+            token_series_id: &token_series_id,
+            old_creator_id: &old_creator_id,
+            new_creator_id: &new_creator_id,
+        }
+        .emit();
+        self.internal_storage_charge(&new_creator_id, initial_storage_usage);
+    }
+}
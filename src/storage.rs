@@ -0,0 +1,95 @@
+use near_contract_standards::storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement};
+use near_sdk::log;
+
+use crate::*;
+
+impl Contract {
+    fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.accounts.get(account_id).map(|balance| StorageBalance {
+            total: balance.into(),
+            available: balance.saturating_sub(self.internal_min_storage_balance()).into(),
+        })
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount: Balance = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let registration_only = registration_only.unwrap_or(false);
+
+        if self.accounts.contains_key(&account_id) && registration_only {
+            log!("The account is already registered, refunding the deposit");
+            if amount > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(amount);
+            }
+        } else if !self.accounts.contains_key(&account_id) && registration_only {
+            let min_balance = self.internal_min_storage_balance();
+            self.internal_storage_deposit(&account_id, min_balance.min(amount));
+            if amount > min_balance {
+                Promise::new(env::predecessor_account_id()).transfer(amount - min_balance);
+            }
+        } else {
+            self.internal_storage_deposit(&account_id, amount);
+        }
+
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let available = self.internal_storage_available(&account_id);
+        let amount: Balance = amount.map(|a| a.0).unwrap_or(available);
+        assert!(
+            amount <= available,
+            "The amount is greater than the available storage balance"
+        );
+
+        if amount > 0 {
+            let balance = self.accounts.get(&account_id).unwrap();
+            self.accounts.insert(&account_id, &(balance - amount));
+            Promise::new(account_id.clone()).transfer(amount);
+        }
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// Storage already spent on series and tokens stays with them, and proceeds are
+    /// tracked separately, so `force` has nothing extra to burn here.
+    #[payable]
+    #[allow(unused_variables)]
+    fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        match self.accounts.remove(&account_id) {
+            Some(balance) => {
+                if balance > 0 {
+                    Promise::new(account_id).transfer(balance);
+                }
+                true
+            }
+            None => {
+                log!("The account {} is not registered", &account_id);
+                false
+            }
+        }
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: self.internal_min_storage_balance().into(),
+            max: None,
+        }
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(&account_id)
+    }
+}
@@ -1,26 +1,134 @@
 
-use near_sdk::{Balance, StorageUsage};
+use near_sdk::Balance;
 
 use crate::*;
 
-pub(crate) fn refund_extra_storage_deposit(storage_used: StorageUsage, used_balance: Balance) {
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-    let attached_deposit = env::attached_deposit()
-        .checked_sub(used_balance)
-        .expect("not enough attached balance");
+pub(crate) fn royalty_to_payout(a: u32, b: Balance) -> U128 {
+    U128(a as u128 * b / 10_000u128)
+}
+
+/// Builds the metadata of one edition of a series, e.g. "Sunset #3/100".
+pub(crate) fn token_metadata_for_edition(token_series: &TokenSeries, edition: u64, issued_at: Option<String>) -> TokenMetadata {
+    let format = &token_series.title_format;
+    let mut title = format!(
+        "{}{}{}",
+        token_series.metadata.title.clone().unwrap_or_default(),
+        format.title_delimeter,
+        edition
+    );
+    if let (Some(edition_delimeter), Some(copies)) = (&format.edition_delimeter, token_series.metadata.copies) {
+        title = format!("{}{}{}", title, edition_delimeter, copies);
+    }
+
+    TokenMetadata {
+        title: Some(title),
+        issued_at,
+        ..token_series.metadata.clone()
+    }
+}
 
+pub(crate) fn assert_valid_royalty(royalty: &HashMap<AccountId, u32>) {
+    assert!(royalty.len() <= 10, " royalty exceeds 10 accounts");
     assert!(
-        required_cost <= attached_deposit,
-        "not enough attached balance {}",
-        required_cost,
+        royalty.values().map(|bps| *bps as u64).sum::<u64>() <= 9000,
+        "Exceeds maximum royalty -> 9000",
     );
+}
 
-    let refund = attached_deposit - required_cost;
-    if refund > 1 {
-        Promise::new(env::predecessor_account_id()).transfer(refund);
+pub(crate) fn assert_valid_primary_split(primary_split: &HashMap<AccountId, u32>) {
+    assert!(primary_split.len() <= 10, " primary_split exceeds 10 accounts");
+    assert_eq!(
+        primary_split.values().map(|bps| *bps as u64).sum::<u64>(),
+        10_000,
+        " primary_split must sum to 10000"
+    );
+}
+
+/// Payment currencies are `near` or the account of a fungible token contract.
+pub(crate) fn assert_valid_currency(currency: &str) {
+    assert!(
+        currency == NEAR || currency.parse::<AccountId>().is_ok(),
+        " invalid currency : {}",
+        currency
+    );
+}
+
+pub(crate) fn assert_valid_presale(presale: &Presale) {
+    assert!(
+        presale.prices.len() <= MAX_SERIES_CURRENCIES,
+        " presale exceeds {} currencies",
+        MAX_SERIES_CURRENCIES
+    );
+    for currency in presale.prices.keys() {
+        assert_valid_currency(currency);
     }
 }
 
-pub(crate) fn royalty_to_payout(a: u32, b: Balance) -> U128 {
-    U128(a as u128 * b / 10_000u128)
+/// Requires a full access key like `assert_one_yocto`, but lets the caller attach a storage deposit.
+pub(crate) fn assert_at_least_one_yocto() {
+    assert!(env::attached_deposit() >= 1, "Requires attached deposit of at least 1 yoctoNEAR");
+}
+
+pub(crate) fn edition_of(token_id: &TokenId) -> u64 {
+    token_id
+        .split(TOKEN_DELIMETER)
+        .nth(1)
+        .and_then(|edition| edition.parse().ok())
+        .expect("Invalid token id")
+}
+
+/// The part of a token's metadata stored per token; everything else comes from its series.
+pub(crate) fn token_metadata_overrides(issued_at: Option<String>) -> TokenMetadata {
+    TokenMetadata {
+        title: None,
+        description: None,
+        media: None,
+        media_hash: None,
+        copies: None,
+        issued_at,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
+
+/// Whether a token stores a full metadata copy rather than overrides: overrides never carry a title,
+/// full copies always do.
+pub(crate) fn is_full_metadata_copy(stored: &Option<TokenMetadata>) -> bool {
+    matches!(stored, Some(metadata) if metadata.title.is_some())
+}
+
+pub(crate) fn merge_token_metadata(base: TokenMetadata, overrides: TokenMetadata) -> TokenMetadata {
+    TokenMetadata {
+        title: overrides.title.or(base.title),
+        description: overrides.description.or(base.description),
+        media: overrides.media.or(base.media),
+        media_hash: overrides.media_hash.or(base.media_hash),
+        copies: overrides.copies.or(base.copies),
+        issued_at: overrides.issued_at.or(base.issued_at),
+        expires_at: overrides.expires_at.or(base.expires_at),
+        starts_at: overrides.starts_at.or(base.starts_at),
+        updated_at: overrides.updated_at.or(base.updated_at),
+        extra: overrides.extra.or(base.extra),
+        reference: overrides.reference.or(base.reference),
+        reference_hash: overrides.reference_hash.or(base.reference_hash),
+    }
+}
+
+pub(crate) fn check_sale_open(token_series: &TokenSeries) -> Result<(), String> {
+    let now = env::block_timestamp();
+    if let Some(mint_start) = token_series.mint_start {
+        if now < mint_start {
+            return Err(format!(" sale not started, starts at {}", mint_start));
+        }
+    }
+    if let Some(mint_end) = token_series.mint_end {
+        if now >= mint_end {
+            return Err(format!(" sale ended at {}", mint_end));
+        }
+    }
+    Ok(())
 }
@@ -7,8 +7,62 @@ pub struct TokenSeriesInfo {
 	metadata: TokenMetadata,
 	creator_id: AccountId,
     royalty: HashMap<AccountId, u32>,
-    ft_token_id: Option<AccountId>,
-    mint_price: Option<U128>
+    prices: HashMap<String, PricingMode>,
+    mint_prices: HashMap<String, U128>,
+    metadata_version: U64,
+    metadata_frozen: bool,
+    burned: U64,
+    remint_burned: bool,
+    burnable_by: BurnPolicy,
+    transferable: bool,
+    transfer_locked_until: Option<U64>,
+    lock_duration_after_mint: Option<U64>,
+    mint_start: Option<U64>,
+    mint_end: Option<U64>,
+    presale: Option<Presale>,
+    allowlist_root: Option<Base64VecU8>,
+    max_per_account: Option<u32>,
+    referral_bps: u32,
+    primary_split: HashMap<AccountId, u32>,
+    pending_creator_id: Option<AccountId>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReferrerStats {
+    mints: U64,
+    earned: HashMap<String, U128>,
+    claimable: HashMap<String, U128>
+}
+
+impl TokenSeriesInfo {
+    fn new(token_series_id: TokenSeriesId, token_series: TokenSeries) -> Self {
+        let mint_prices = token_series.current_prices();
+        TokenSeriesInfo {
+            token_series_id,
+            metadata: token_series.metadata,
+            creator_id: token_series.creator_id,
+            royalty: token_series.royalty,
+            prices: token_series.prices,
+            mint_prices,
+            metadata_version: U64::from(token_series.metadata_versions.len()),
+            metadata_frozen: token_series.metadata_frozen,
+            burned: U64::from(token_series.burned),
+            remint_burned: token_series.remint_burned,
+            burnable_by: token_series.burnable_by,
+            transferable: token_series.transferable,
+            transfer_locked_until: token_series.transfer_locked_until.map(U64::from),
+            lock_duration_after_mint: token_series.lock_duration_after_mint.map(U64::from),
+            mint_start: token_series.mint_start.map(U64::from),
+            mint_end: token_series.mint_end.map(U64::from),
+            presale: token_series.presale,
+            allowlist_root: token_series.allowlist_root.map(Base64VecU8::from),
+            max_per_account: token_series.max_per_account,
+            referral_bps: token_series.referral_bps,
+            primary_split: token_series.primary_split,
+            pending_creator_id: token_series.pending_creator_id
+        }
+    }
 }
 
 #[near_bindgen]
@@ -19,32 +73,35 @@ impl Contract {
 
     pub fn nft_get_series_single(&self, token_series_id: TokenSeriesId) -> TokenSeriesInfo {
         let token_series = self.token_series_by_id.get(&token_series_id).expect("Series does not exist");
-        let price = match token_series.price {
-            Some(p) => Some(U128::from(p)),
-            None =>  None
-        };
-        TokenSeriesInfo{
-            token_series_id,
-            metadata: token_series.metadata,
-            creator_id: token_series.creator_id,
-            royalty: token_series.royalty,
-            mint_price: price,
-            ft_token_id: token_series.ft_token_id
-        }
+        TokenSeriesInfo::new(token_series_id, token_series)
     }
     
-    pub fn nft_get_series_format(self) -> (char, &'static str, &'static str) {
-        (TOKEN_DELIMETER, TITLE_DELIMETER, EDITION_DELIMETER)
+    pub fn nft_get_series_format(&self, token_series_id: Option<TokenSeriesId>) -> (char, String, Option<String>) {
+        let title_format = match token_series_id {
+            Some(token_series_id) => self.token_series_by_id.get(&token_series_id).expect("Token series not exist").title_format,
+            None => TitleFormat::default()
+        };
+        (TOKEN_DELIMETER, title_format.title_delimeter, title_format.edition_delimeter)
     }
     
-    pub fn nft_get_series_price(self, token_series_id: TokenSeriesId) -> Option<U128> {
-        let price = self.token_series_by_id.get(&token_series_id).unwrap().price;
-        match price {
-            Some(p) => return Some(U128::from(p)),
-            None => return None
-        };
+    /// Current mint price per accepted currency, keyed by `near` or a fungible token contract
+    pub fn nft_get_series_price(self, token_series_id: TokenSeriesId) -> HashMap<String, U128> {
+        self.token_series_by_id.get(&token_series_id).expect("Token series not exist").current_prices()
     }
     
+    /// Total public price in NEAR, or in `ft_token_id` when given, of minting `count` more tokens of the series right now.
+    /// `None` when the total is out of the balance range.
+    pub fn nft_quote_series_mints(&self, token_series_id: TokenSeriesId, count: u64, ft_token_id: Option<AccountId>) -> Option<U128> {
+        assert!(count <= MAX_BATCH_MINT_SIZE, " exceeds max batch size : {}", MAX_BATCH_MINT_SIZE);
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+        let pricing = token_series.prices.get(currency).expect(" not for sale");
+        let supply = token_series.tokens.len();
+        (supply..supply + count)
+            .try_fold(0u128, |total, supply| total.checked_add(pricing.price_at(env::block_timestamp(), supply)?))
+            .map(U128)
+    }
+
     pub fn nft_get_series(
         &self,
         from_index: Option<U128>,
@@ -62,24 +119,48 @@ impl Contract {
             .iter()
             .skip(start_index as usize)
             .take(limit)
-            .map(|(token_series_id, token_series)| {
-                let price = match token_series.price {
-                    Some(p) => Some(U128::from(p)),
-                    None =>  None
-                };
-                TokenSeriesInfo{
-                    token_series_id,
-                    metadata: token_series.metadata,
-                    creator_id: token_series.creator_id,
-                    royalty: token_series.royalty,
-                    mint_price: price,
-                    ft_token_id: token_series.ft_token_id
-                }
-            }
-            )
+            .map(|(token_series_id, token_series)| TokenSeriesInfo::new(token_series_id, token_series))
             .collect()
     }
     
+    /// Returns the previous metadata of a series, oldest first. The current metadata is version `len`.
+    pub fn nft_get_series_metadata_history(
+        &self,
+        token_series_id: TokenSeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<TokenMetadata> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        let metadata_versions = self.token_series_by_id.get(&token_series_id).expect("Token series not exist").metadata_versions;
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+
+        metadata_versions
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .collect()
+    }
+
+    /// Past creator handoffs of a series, oldest first
+    pub fn nft_series_creator_history(
+        &self,
+        token_series_id: TokenSeriesId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<CreatorTransfer> {
+        let start_index: u128 = from_index.map(From::from).unwrap_or_default();
+        let creator_history = self.token_series_by_id.get(&token_series_id).expect("Token series not exist").creator_history;
+        let limit = limit.map(|v| v as usize).unwrap_or(usize::MAX);
+        assert_ne!(limit, 0, "Cannot provide limit of 0.");
+
+        creator_history
+            .iter()
+            .skip(start_index as usize)
+            .take(limit)
+            .collect()
+    }
+
     pub fn nft_supply_for_series(&self, token_series_id: TokenSeriesId) -> U64 {
         self.token_series_by_id.get(&token_series_id).expect("Token series not exist").tokens.len().into()
     }
@@ -103,11 +184,74 @@ impl Contract {
             .iter()
             .skip(start_index as usize)
             .take(limit)
-            .map(|token_id| self.nft_token(token_id).unwrap())
+            .filter_map(|token_id| self.nft_token(token_id))
             .collect()
     }
 
+    pub fn creator_proceeds_of(&self, account_id: AccountId, ft_token_id: Option<AccountId>) -> U128 {
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+        let proceeds = self.proceeds.get(&account_id).unwrap_or_default();
+        let amount: Balance = proceeds.get(currency).map_or(0, |by_series| by_series.values().sum());
+        U128::from(amount)
+    }
+
+    /// Unclaimed proceeds of a series owed to `account_id`, the creator when not given,
+    /// in NEAR or in `ft_token_id` when given.
+    pub fn nft_series_proceeds(&self, token_series_id: TokenSeriesId, ft_token_id: Option<AccountId>, account_id: Option<AccountId>) -> U128 {
+        let token_series = self.token_series_by_id.get(&token_series_id).expect("Token series not exist");
+        let currency = ft_token_id.as_ref().map_or(NEAR, |id| id.as_str());
+        let account_id = account_id.unwrap_or(token_series.creator_id);
+        let proceeds = self.proceeds.get(&account_id).unwrap_or_default();
+        let amount = proceeds
+            .get(currency)
+            .and_then(|by_series| by_series.get(&token_series_id).copied())
+            .unwrap_or(0);
+        U128::from(amount)
+    }
+
+    pub fn nft_allowlist_quota(&self, token_series_id: TokenSeriesId, account_id: AccountId) -> Option<u32> {
+        self.allowlist.get(&(token_series_id, account_id))
+    }
+
+    pub fn nft_minted_count(&self, token_series_id: TokenSeriesId, account_id: AccountId) -> u32 {
+        self.minted_per_account.get(&(token_series_id, account_id)).unwrap_or(0)
+    }
+
+    /// Returns the block timestamp (in nanoseconds) at which a still locked token becomes transferable.
+    pub fn nft_token_unlock_at(&self, token_id: TokenId) -> Option<U64> {
+        self.locked_until_by_id
+            .get(&token_id)
+            .filter(|locked_until| *locked_until > env::block_timestamp())
+            .map(U64::from)
+    }
+
+    pub fn get_platform_fee(&self) -> u32 {
+        self.platform_fee_bps
+    }
+
+    /// Unclaimed platform fees by currency, keyed by `near` or a fungible token contract
+    pub fn get_platform_fees(&self) -> HashMap<String, U128> {
+        self.platform_fees.iter().map(|(currency, amount)| (currency.clone(), U128(*amount))).collect()
+    }
+
+    /// Referred mints and rewards of `referrer_id` by currency, keyed by `near` or a fungible token contract
+    pub fn nft_referrer_stats(&self, referrer_id: AccountId) -> ReferrerStats {
+        let referrer = self.referrers.get(&referrer_id).unwrap_or_default();
+        let to_json = |balances: HashMap<String, Balance>| {
+            balances.into_iter().map(|(currency, amount)| (currency, U128(amount))).collect()
+        };
+        ReferrerStats {
+            mints: referrer.mints.into(),
+            earned: to_json(referrer.earned),
+            claimable: to_json(referrer.claimable)
+        }
+    }
+
+    pub fn nft_batch_mint_limit(&self) -> u64 {
+        MAX_BATCH_MINT_SIZE
+    }
+
     pub fn get_storage_fee(&self) -> U128 {
-        (self.tokens.extra_storage_in_bytes_per_token as u128 * env::storage_byte_cost()).into()
+        (self.token_storage_usage as u128 * env::storage_byte_cost()).into()
     }
 }